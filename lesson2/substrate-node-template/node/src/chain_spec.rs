@@ -1,9 +1,19 @@
+use std::collections::BTreeMap;
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	AccountId, Balance, BalancesConfig, ContractsConfig, CouncilConfig, DemocracyConfig, EVMConfig,
+	GenesisConfig, GrandpaConfig, SessionConfig, StakingConfig, SudoConfig, SystemConfig,
+	TechnicalCommitteeConfig, TemplateModuleConfig, TreasuryConfig, VestingConfig,
+	WASM_BINARY, Signature, Perbill, StakerStatus, BlockNumber, DAYS, opaque::SessionKeys,
 };
+#[cfg(not(feature = "babe-consensus"))]
+use node_template_runtime::AuraConfig;
+#[cfg(feature = "babe-consensus")]
+use node_template_runtime::BabeConfig;
+#[cfg(not(feature = "babe-consensus"))]
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+#[cfg(feature = "babe-consensus")]
+use sp_consensus_babe::AuthorityId as BabeId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
 use sp_runtime::traits::{Verify, IdentifyAccount};
 use sc_service::ChainType;
@@ -11,6 +21,17 @@ use sc_service::ChainType;
 // Note this is the URL for the telemetry server
 //const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
+/// The balance every endowed account starts with.
+const ENDOWMENT: Balance = 1 << 60;
+/// The amount each genesis validator bonds from its stash.
+const STASH: Balance = ENDOWMENT / 1_000;
+/// The part of an endowment that is not subject to vesting.
+const LIQUID: Balance = ENDOWMENT / 10;
+/// The block from which vested endowments start to unlock.
+const VESTING_START: BlockNumber = 0;
+/// The number of blocks over which vested endowments unlock linearly.
+const VESTING_DURATION: BlockNumber = 365 * DAYS;
+
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
 pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig>;
 
@@ -30,14 +51,36 @@ pub fn get_account_id_from_seed<TPublic: Public>(seed: &str) -> AccountId where
 	AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
 
-/// Helper function to generate an authority key for Aura
-pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
+/// The block authoring key of the selected consensus engine.
+#[cfg(not(feature = "babe-consensus"))]
+pub type AuthorId = AuraId;
+/// The block authoring key of the selected consensus engine.
+#[cfg(feature = "babe-consensus")]
+pub type AuthorId = BabeId;
+
+/// Helper function to generate the stash and controller accounts and the authority keys
+/// for block authoring and GRANDPA
+pub fn authority_keys_from_seed(s: &str) -> (AccountId, AccountId, AuthorId, GrandpaId) {
 	(
-		get_from_seed::<AuraId>(s),
+		get_account_id_from_seed::<sr25519::Public>(&format!("{}//stash", s)),
+		get_account_id_from_seed::<sr25519::Public>(s),
+		get_from_seed::<AuthorId>(s),
 		get_from_seed::<GrandpaId>(s),
 	)
 }
 
+/// The session keys of an authority.
+#[cfg(not(feature = "babe-consensus"))]
+pub fn session_keys(aura: AuraId, grandpa: GrandpaId) -> SessionKeys {
+	SessionKeys { aura, grandpa }
+}
+
+/// The session keys of an authority.
+#[cfg(feature = "babe-consensus")]
+pub fn session_keys(babe: BabeId, grandpa: GrandpaId) -> SessionKeys {
+	SessionKeys { babe, grandpa }
+}
+
 pub fn development_config() -> ChainSpec {
 	ChainSpec::from_genesis(
 		"Development",
@@ -99,23 +142,82 @@ pub fn local_testnet_config() -> ChainSpec {
 	)
 }
 
-fn testnet_genesis(initial_authorities: Vec<(AuraId, GrandpaId)>,
+fn testnet_genesis(initial_authorities: Vec<(AccountId, AccountId, AuthorId, GrandpaId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
-	_enable_println: bool) -> GenesisConfig {
+	enable_println: bool) -> GenesisConfig {
+	// The first half of the endowed accounts sit on the council and the technical committee.
+	let num_endowed_accounts = endowed_accounts.len();
+	let council_members: Vec<AccountId> = endowed_accounts.iter()
+		.take((num_endowed_accounts + 1) / 2)
+		.cloned()
+		.collect();
+
 	GenesisConfig {
 		system: Some(SystemConfig {
 			code: WASM_BINARY.to_vec(),
 			changes_trie_config: Default::default(),
 		}),
 		balances: Some(BalancesConfig {
-			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
+			balances: endowed_accounts.iter().cloned().map(|k|(k, ENDOWMENT)).collect(),
+		}),
+		staking: Some(StakingConfig {
+			validator_count: initial_authorities.len() as u32 * 2,
+			minimum_validator_count: initial_authorities.len() as u32,
+			stakers: initial_authorities.iter().map(|x| {
+				(x.0.clone(), x.1.clone(), STASH, StakerStatus::Validator)
+			}).collect(),
+			invulnerables: initial_authorities.iter().map(|x| x.0.clone()).collect(),
+			slash_reward_fraction: Perbill::from_percent(10),
+			.. Default::default()
 		}),
+		session: Some(SessionConfig {
+			keys: initial_authorities.iter().map(|x| {
+				(x.0.clone(), x.0.clone(), session_keys(x.2.clone(), x.3.clone()))
+			}).collect(),
+		}),
+		// Block authoring and GRANDPA authorities are set by the session pallet from the keys above.
+		#[cfg(not(feature = "babe-consensus"))]
 		aura: Some(AuraConfig {
-			authorities: initial_authorities.iter().map(|x| (x.0.clone())).collect(),
+			authorities: vec![],
+		}),
+		#[cfg(feature = "babe-consensus")]
+		babe: Some(BabeConfig {
+			authorities: vec![],
 		}),
 		grandpa: Some(GrandpaConfig {
-			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
+			authorities: vec![],
+		}),
+		contracts: Some(ContractsConfig {
+			current_schedule: pallet_contracts::Schedule {
+				enable_println,
+				..Default::default()
+			},
+		}),
+		evm: Some(EVMConfig {
+			accounts: BTreeMap::new(),
+		}),
+		democracy: Some(DemocracyConfig::default()),
+		collective_Instance1: Some(CouncilConfig {
+			members: council_members.clone(),
+			phantom: Default::default(),
+		}),
+		collective_Instance2: Some(TechnicalCommitteeConfig {
+			members: council_members,
+			phantom: Default::default(),
+		}),
+		treasury: Some(TreasuryConfig::default()),
+		// All endowments but the root key's unlock linearly over a year, apart from `LIQUID`.
+		vesting: Some(VestingConfig {
+			vesting: endowed_accounts.iter()
+				.filter(|k| **k != root_key)
+				.cloned()
+				.map(|k| (k, VESTING_START, VESTING_DURATION, LIQUID))
+				.collect(),
+		}),
+		// The root key also acts as identity registrar, judging the identities of account owners.
+		template: Some(TemplateModuleConfig {
+			identity_registrars: vec![root_key.clone()],
 		}),
 		sudo: Some(SudoConfig {
 			key: root_key,
@@ -1,6 +1,17 @@
-use sc_cli::{RunCmd, Subcommand};
+use std::path::PathBuf;
+
+use sc_cli::RunCmd;
 use structopt::StructOpt;
 
+use crate::{
+	keys::KeysCmd,
+	metadata_cmd::MetadataDiffCmd,
+	sign::SignTransactionCmd,
+	state::{ExportStateCmd, ImportStateCmd},
+	try_runtime::TryRuntimeCmd,
+	upgrade::UpgradeCheckCmd,
+};
+
 #[derive(Debug, StructOpt)]
 pub struct Cli {
 	#[structopt(subcommand)]
@@ -8,4 +19,40 @@ pub struct Cli {
 
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// Directory recording the slot of every block this node authors, to never author two
+	/// blocks for a slot. Nodes sharing an authority key must share it. Defaults to
+	/// `slashing-protection` next to the keystore.
+	#[structopt(long, parse(from_os_str))]
+	pub slashing_protection: Option<PathBuf>,
+}
+
+/// Possible subcommands of the main binary.
+#[derive(Debug, StructOpt)]
+pub enum Subcommand {
+	/// A set of base subcommands handled by `sc_cli`.
+	#[structopt(flatten)]
+	Base(sc_cli::Subcommand),
+
+	/// Export the state of the chain at a block into a snapshot file.
+	ExportState(ExportStateCmd),
+
+	/// Build a chain spec for a new chain whose genesis state is a snapshot.
+	ImportState(ImportStateCmd),
+
+	/// Manage the session keys in the node's keystore.
+	Keys(KeysCmd),
+
+	/// Report the metadata changes between two runtimes and whether they break transactions.
+	MetadataDiff(MetadataDiffCmd),
+
+	/// Build and sign a transaction offline.
+	SignTransaction(SignTransactionCmd),
+
+	/// Dry-run the `on_runtime_upgrade` hooks of a new runtime against the chain's state.
+	TryRuntime(TryRuntimeCmd),
+
+	/// Check a new runtime's version and metadata against the chain's and build the call that
+	/// enacts it.
+	UpgradeCheck(UpgradeCheckCmd),
 }
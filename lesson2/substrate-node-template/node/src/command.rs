@@ -16,7 +16,8 @@
 // limitations under the License.
 
 use crate::chain_spec;
-use crate::cli::Cli;
+use crate::cli::{Cli, Subcommand};
+use crate::keys::KeysCmd;
 use crate::service;
 use sc_cli::SubstrateCli;
 
@@ -65,15 +66,46 @@ pub fn run() -> sc_cli::Result<()> {
 	let cli = Cli::from_args();
 
 	match &cli.subcommand {
-		Some(subcommand) => {
+		Some(Subcommand::Base(subcommand)) => {
 			let runner = cli.create_runner(subcommand)?;
 			runner.run_subcommand(subcommand, |config| Ok(new_full_start!(config).0))
 		}
+		Some(Subcommand::ExportState(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config))
+		}
+		Some(Subcommand::ImportState(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config))
+		}
+		Some(Subcommand::Keys(KeysCmd::Generate(cmd))) => cmd.run(),
+		Some(Subcommand::Keys(KeysCmd::Insert(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config))
+		}
+		Some(Subcommand::Keys(KeysCmd::Rotate(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config))
+		}
+		Some(Subcommand::MetadataDiff(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config))
+		}
+		Some(Subcommand::SignTransaction(cmd)) => cmd.run(),
+		Some(Subcommand::TryRuntime(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config))
+		}
+		Some(Subcommand::UpgradeCheck(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config))
+		}
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
+			let slashing_protection = cli.slashing_protection.clone();
 			runner.run_node(
 				service::new_light,
-				service::new_full,
+				|config| service::new_full(config, slashing_protection),
 				node_template_runtime::VERSION
 			)
 		}
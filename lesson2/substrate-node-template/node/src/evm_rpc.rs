@@ -0,0 +1,154 @@
+//! Ethereum style RPC methods for the EVM pallet.
+//!
+//! The methods mirror their `eth_*` counterparts, but take EVM addresses as they are mapped
+//! from accounts by the runtime, see `evm_addressOf`. State changing EVM calls are submitted as
+//! `EVM::call` extrinsics; `evm_call` only dry-runs them.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use node_template_runtime::{opaque::Block, AccountId, EvmRuntimeApi, Hash};
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{Bytes, H160, H256, U256};
+use sp_runtime::generic::BlockId;
+
+/// Error code for failures of the runtime API.
+const RUNTIME_ERROR: i64 = 1;
+
+/// The largest gas limit `evm_call` accepts.
+const MAX_GAS_LIMIT: u32 = 10_000_000;
+
+/// A call to dry-run with `evm_call`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct CallRequest {
+	from: H160,
+	to: H160,
+	#[serde(default)]
+	data: Option<Bytes>,
+	#[serde(default)]
+	value: U256,
+	gas_limit: u32,
+}
+
+/// The outcome of `evm_call`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallResult {
+	/// Whether the call succeeded.
+	success: bool,
+	/// The gas the call used, including the transaction cost.
+	used_gas: U256,
+}
+
+/// Ethereum style EVM RPC methods.
+#[rpc]
+pub trait EvmApi<BlockHash> {
+	/// The EVM address of `account`.
+	#[rpc(name = "evm_addressOf")]
+	fn address_of(&self, account: AccountId, at: Option<BlockHash>) -> Result<H160>;
+
+	/// The balance of the EVM account at `address`.
+	#[rpc(name = "evm_getBalance")]
+	fn balance(&self, address: H160, at: Option<BlockHash>) -> Result<U256>;
+
+	/// The nonce of the EVM account at `address`.
+	#[rpc(name = "evm_getTransactionCount")]
+	fn transaction_count(&self, address: H160, at: Option<BlockHash>) -> Result<U256>;
+
+	/// The code of the contract at `address`.
+	#[rpc(name = "evm_getCode")]
+	fn code_at(&self, address: H160, at: Option<BlockHash>) -> Result<Bytes>;
+
+	/// Slot `index` of the storage of the contract at `address`.
+	#[rpc(name = "evm_getStorageAt")]
+	fn storage_at(&self, address: H160, index: H256, at: Option<BlockHash>) -> Result<H256>;
+
+	/// Dry-runs a call without submitting a transaction. Nothing is charged and state changes
+	/// are discarded.
+	#[rpc(name = "evm_call")]
+	fn call(&self, request: CallRequest, at: Option<BlockHash>) -> Result<CallResult>;
+}
+
+/// Implements the EVM RPC methods with the `EvmRuntimeApi` of the runtime.
+pub struct Evm<C> {
+	client: Arc<C>,
+}
+
+impl<C> Evm<C> {
+	/// Create new `Evm` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Evm { client }
+	}
+}
+
+impl<C> Evm<C> where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+{
+	/// The block to query, the best block if none is given.
+	fn at(&self, at: Option<Hash>) -> BlockId<Block> {
+		BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash))
+	}
+}
+
+impl<C> EvmApi<Hash> for Evm<C> where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: EvmRuntimeApi<Block>,
+{
+	fn address_of(&self, account: AccountId, at: Option<Hash>) -> Result<H160> {
+		self.client.runtime_api().address_of(&self.at(at), account).map_err(runtime_error)
+	}
+
+	fn balance(&self, address: H160, at: Option<Hash>) -> Result<U256> {
+		self.client.runtime_api().account_basic(&self.at(at), address)
+			.map(|account| account.balance)
+			.map_err(runtime_error)
+	}
+
+	fn transaction_count(&self, address: H160, at: Option<Hash>) -> Result<U256> {
+		self.client.runtime_api().account_basic(&self.at(at), address)
+			.map(|account| account.nonce)
+			.map_err(runtime_error)
+	}
+
+	fn code_at(&self, address: H160, at: Option<Hash>) -> Result<Bytes> {
+		self.client.runtime_api().account_code(&self.at(at), address)
+			.map(Into::into)
+			.map_err(runtime_error)
+	}
+
+	fn storage_at(&self, address: H160, index: H256, at: Option<Hash>) -> Result<H256> {
+		self.client.runtime_api().storage_at(&self.at(at), address, index).map_err(runtime_error)
+	}
+
+	fn call(&self, request: CallRequest, at: Option<Hash>) -> Result<CallResult> {
+		let CallRequest { from, to, data, value, gas_limit } = request;
+		if gas_limit > MAX_GAS_LIMIT {
+			return Err(Error {
+				code: ErrorCode::InvalidParams,
+				message: format!(
+					"Requested gas limit is greater than maximum allowed: {} > {}",
+					gas_limit, MAX_GAS_LIMIT,
+				),
+				data: None,
+			});
+		}
+
+		let (success, used_gas) = self.client.runtime_api()
+			.call(&self.at(at), from, to, data.map_or_else(Vec::new, |data| data.to_vec()), value, gas_limit)
+			.map_err(runtime_error)?;
+		Ok(CallResult { success, used_gas })
+	}
+}
+
+fn runtime_error(err: impl std::fmt::Debug) -> Error {
+	Error {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Runtime trapped".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
@@ -0,0 +1,146 @@
+//! The `keys` subcommands, which manage the session keys in the node's keystore.
+
+use codec::Encode;
+use node_template_runtime::opaque::SessionKeys;
+use sc_cli::{CliConfiguration, KeystoreParams, SharedParams};
+use sc_keystore::Store;
+use sc_service::{config::KeystoreConfig, Configuration};
+use sp_application_crypto::{AppKey, AppPair};
+use sp_core::{crypto::Pair, hexdisplay::HexDisplay, sr25519};
+use sp_finality_grandpa::AuthorityId as GrandpaId;
+use sp_runtime::traits::OpaqueKeys;
+use structopt::StructOpt;
+
+use crate::chain_spec::{session_keys, AuthorId};
+
+/// Manage the session keys of this node, i.e. its block authoring and GRANDPA keys.
+///
+/// Every command prints the SCALE-encoded `SessionKeys`, to be registered on chain with
+/// `session.setKeys`.
+#[derive(Debug, StructOpt)]
+pub enum KeysCmd {
+	/// Generate a new mnemonic and print the session keys derived from it. The keystore is not
+	/// touched; keep the mnemonic and pass it to `keys insert`.
+	Generate(GenerateKeysCmd),
+
+	/// Insert the session keys derived from a secret key URI into the keystore.
+	Insert(InsertKeysCmd),
+
+	/// Generate new random session keys in the keystore.
+	Rotate(RotateKeysCmd),
+}
+
+/// The `keys generate` command.
+#[derive(Debug, StructOpt)]
+pub struct GenerateKeysCmd {}
+
+impl GenerateKeysCmd {
+	/// Run the command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let (_, phrase, _) = sr25519::Pair::generate_with_phrase(None);
+		let from_phrase = |e| sc_cli::Error::Input(format!("Invalid mnemonic: {:?}", e));
+		let keys = session_keys(
+			<AuthorId as AppKey>::Pair::from_string(&phrase, None).map_err(from_phrase)?.public(),
+			<GrandpaId as AppKey>::Pair::from_string(&phrase, None).map_err(from_phrase)?.public(),
+		);
+
+		println!("Secret phrase: {}", phrase);
+		print_keys(&keys);
+		Ok(())
+	}
+}
+
+/// The `keys insert` command.
+#[derive(Debug, StructOpt)]
+pub struct InsertKeysCmd {
+	/// The secret key URI both session keys are derived from, e.g. a mnemonic or `//Alice`.
+	#[structopt(long)]
+	pub suri: String,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+impl InsertKeysCmd {
+	/// Run the command.
+	pub fn run(&self, config: Configuration) -> sc_cli::Result<()> {
+		print_keys(&write_session_keys(&config, Some(&self.suri))?);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for InsertKeysCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+}
+
+/// The `keys rotate` command.
+#[derive(Debug, StructOpt)]
+pub struct RotateKeysCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+impl RotateKeysCmd {
+	/// Run the command.
+	pub fn run(&self, config: Configuration) -> sc_cli::Result<()> {
+		print_keys(&write_session_keys(&config, None)?);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for RotateKeysCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn keystore_params(&self) -> Option<&KeystoreParams> {
+		Some(&self.keystore_params)
+	}
+}
+
+/// Write one key of type `P` into the keystore, derived from `suri` or random without one.
+fn write_key<P: AppPair>(store: &mut Store, suri: Option<&str>) -> Result<<P as Pair>::Public, String> {
+	let pair = match suri {
+		Some(suri) => store.insert::<P>(suri),
+		None => store.generate::<P>(),
+	};
+	pair.map(|pair| pair.public())
+		.map_err(|e| format!("Failed to write the {} key: {}", String::from_utf8_lossy(&P::ID.0), e))
+}
+
+/// Write all session keys into the node's keystore.
+fn write_session_keys(config: &Configuration, suri: Option<&str>) -> Result<SessionKeys, String> {
+	let store = match &config.keystore {
+		KeystoreConfig::Path { path, password } => Store::open(path.clone(), password.clone())
+			.map_err(|e| format!("Failed to open the keystore: {}", e))?,
+		_ => return Err("The keystore must be on disk".into()),
+	};
+	let mut store = store.write();
+
+	Ok(session_keys(
+		write_key::<<AuthorId as AppKey>::Pair>(&mut store, suri)?,
+		write_key::<<GrandpaId as AppKey>::Pair>(&mut store, suri)?,
+	))
+}
+
+fn print_keys(keys: &SessionKeys) {
+	println!("Block authoring key: 0x{}", HexDisplay::from(&keys.get_raw(<AuthorId as AppKey>::ID)));
+	println!("GRANDPA key: 0x{}", HexDisplay::from(&keys.get_raw(<GrandpaId as AppKey>::ID)));
+	println!("Session keys: 0x{}", HexDisplay::from(&keys.encode()));
+}
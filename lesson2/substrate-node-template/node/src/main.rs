@@ -5,6 +5,21 @@ mod chain_spec;
 #[macro_use]
 mod service;
 mod cli;
+mod evm_rpc;
+mod keys;
+mod metadata_cmd;
+mod metadata_diff;
+mod rpc;
+mod sign;
+#[cfg(not(feature = "babe-consensus"))]
+mod signing_guard;
+#[cfg(not(feature = "babe-consensus"))]
+mod slashing_protection;
+mod snapshot;
+mod state;
+mod try_runtime;
+mod upgrade;
+mod wasm;
 mod command;
 
 fn main() -> sc_cli::Result<()> {
@@ -0,0 +1,56 @@
+//! The `metadata-diff` subcommand, which reports the changes between two runtimes.
+
+use std::{fs, path::PathBuf};
+
+use sc_cli::{CliConfiguration, SharedParams};
+use sc_service::Configuration;
+use structopt::StructOpt;
+
+use crate::{metadata_diff, wasm};
+
+/// Report the calls, events, storage items, constants and errors that changed between two
+/// runtime WASM blobs.
+///
+/// Fails if the changes break existing transactions but the `transaction_version` was not
+/// increased.
+#[derive(Debug, StructOpt)]
+pub struct MetadataDiffCmd {
+	/// The WASM blob of the old runtime.
+	#[structopt(parse(from_os_str))]
+	pub old: PathBuf,
+
+	/// The WASM blob of the new runtime.
+	#[structopt(parse(from_os_str))]
+	pub new: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl MetadataDiffCmd {
+	/// Run the command.
+	pub fn run(&self, config: Configuration) -> sc_cli::Result<()> {
+		let old_code = fs::read(&self.old)?;
+		let new_code = fs::read(&self.new)?;
+
+		let old_version = wasm::runtime_version(&config, &old_code)?;
+		let new_version = wasm::runtime_version(&config, &new_code)?;
+		println!("Old runtime: {}", old_version);
+		println!("New runtime: {}", new_version);
+
+		let diff = metadata_diff::diff(
+			&wasm::metadata(&config, &old_code)?,
+			&wasm::metadata(&config, &new_code)?,
+		)?;
+		print!("{}", diff);
+		metadata_diff::check_transaction_version(&diff, &old_version, &new_version)?;
+		Ok(())
+	}
+}
+
+impl CliConfiguration for MetadataDiffCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
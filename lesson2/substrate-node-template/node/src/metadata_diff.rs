@@ -0,0 +1,541 @@
+//! Differences between the metadata of two runtimes.
+
+use std::{convert::TryFrom, fmt};
+
+use frame_metadata::{
+	DecodeDifferent, FunctionMetadata, ModuleMetadata, RuntimeMetadata, RuntimeMetadataPrefixed,
+	RuntimeMetadataV11, StorageEntryType,
+};
+use sp_core::hexdisplay::HexDisplay;
+use sp_version::RuntimeVersion;
+
+/// Get the decoded value of a metadata field. Decoded metadata never holds the `Encode` variant.
+fn decoded<B, O>(value: &DecodeDifferent<B, O>) -> &O {
+	match value {
+		DecodeDifferent::Decoded(value) => value,
+		DecodeDifferent::Encode(_) => unreachable!("decoded metadata only holds decoded values; qed"),
+	}
+}
+
+fn v11(metadata: &RuntimeMetadataPrefixed) -> Result<&RuntimeMetadataV11, String> {
+	match &metadata.1 {
+		RuntimeMetadata::V11(metadata) => Ok(metadata),
+		_ => Err("Only metadata V11 is supported".into()),
+	}
+}
+
+/// The modules of the metadata, in the order of their pallets in the runtime.
+pub fn modules(metadata: &RuntimeMetadataPrefixed) -> Result<&[ModuleMetadata], String> {
+	Ok(decoded(&v11(metadata)?.modules).as_slice())
+}
+
+fn module_name(module: &ModuleMetadata) -> &str {
+	decoded(&module.name).as_str()
+}
+
+fn calls(module: &ModuleMetadata) -> &[FunctionMetadata] {
+	module.calls.as_ref().map(|calls| &decoded(calls)[..]).unwrap_or(&[])
+}
+
+/// The module and call index that select a call in an encoded `Call`.
+pub fn call_index(metadata: &RuntimeMetadataPrefixed, module: &str, call: &str) -> Result<[u8; 2], String> {
+	let (module_index, module) = call_modules(modules(metadata)?)
+		.find(|(_, m)| module_name(m) == module)
+		.ok_or_else(|| format!("The runtime has no {} calls", module))?;
+	let call_index = calls(module).iter()
+		.position(|c| decoded(&c.name) == call)
+		.ok_or_else(|| format!("The runtime has no call {}.{}", module_name(module), call))?;
+
+	Ok([encoded_index(module_index)?, encoded_index(call_index)?])
+}
+
+/// The modules with calls and their index in `Call`. Only these are variants of `Call`.
+fn call_modules(modules: &[ModuleMetadata]) -> impl Iterator<Item = (usize, &ModuleMetadata)> {
+	modules.iter()
+		.filter(|m| m.calls.is_some())
+		.enumerate()
+}
+
+/// An enum variant index as SCALE encodes it, in a single byte.
+fn encoded_index(index: usize) -> Result<u8, String> {
+	u8::try_from(index).map_err(|_| format!("Index {} does not fit into an encoded call", index))
+}
+
+/// A named item of a module together with a description of everything that affects how it is
+/// encoded, e.g. `#2 set_code(code: Vec<u8>)` for a call.
+type Item = (String, String);
+
+fn call_items(module: &ModuleMetadata) -> Vec<Item> {
+	calls(module).iter().enumerate()
+		.map(|(index, call)| {
+			let arguments = decoded(&call.arguments).iter()
+				.map(|argument| format!("{}: {}", decoded(&argument.name), decoded(&argument.ty)))
+				.collect::<Vec<_>>();
+			let name = decoded(&call.name).clone();
+			let description = format!("#{} {}({})", index, name, arguments.join(", "));
+			(name, description)
+		})
+		.collect()
+}
+
+fn event_items(module: &ModuleMetadata) -> Vec<Item> {
+	let events = module.event.as_ref().map(|events| &decoded(events)[..]).unwrap_or(&[]);
+	events.iter().enumerate()
+		.map(|(index, event)| {
+			let name = decoded(&event.name).clone();
+			let description = format!("#{} {}({})", index, name, decoded(&event.arguments).join(", "));
+			(name, description)
+		})
+		.collect()
+}
+
+fn storage_items(module: &ModuleMetadata) -> Vec<Item> {
+	let entries = module.storage.as_ref().map(|storage| &decoded(&decoded(storage).entries)[..]).unwrap_or(&[]);
+	entries.iter()
+		.map(|entry| {
+			let name = decoded(&entry.name).clone();
+			let ty = match &entry.ty {
+				StorageEntryType::Plain(value) => decoded(value).clone(),
+				StorageEntryType::Map { hasher, key, value, .. } =>
+					format!("map {:?}({}) => {}", hasher, decoded(key), decoded(value)),
+				StorageEntryType::DoubleMap { hasher, key1, key2, value, key2_hasher } => format!(
+					"double_map {:?}({}), {:?}({}) => {}",
+					hasher, decoded(key1), key2_hasher, decoded(key2), decoded(value),
+				),
+			};
+			let description = format!("{}: {:?} {}", name, entry.modifier, ty);
+			(name, description)
+		})
+		.collect()
+}
+
+fn constant_items(module: &ModuleMetadata) -> Vec<Item> {
+	decoded(&module.constants).iter()
+		.map(|constant| {
+			let name = decoded(&constant.name).clone();
+			let description = format!(
+				"{}: {} = 0x{}",
+				name,
+				decoded(&constant.ty),
+				HexDisplay::from(decoded(&constant.value)),
+			);
+			(name, description)
+		})
+		.collect()
+}
+
+fn error_items(module: &ModuleMetadata) -> Vec<Item> {
+	decoded(&module.errors).iter().enumerate()
+		.map(|(index, error)| {
+			let name = decoded(&error.name).clone();
+			(name.clone(), format!("#{} {}", index, name))
+		})
+		.collect()
+}
+
+/// Added, removed and changed items of one kind, by their descriptions.
+#[derive(Debug, Default)]
+pub struct ItemsDiff {
+	/// The new items.
+	pub added: Vec<String>,
+	/// The removed items.
+	pub removed: Vec<String>,
+	/// Old and new descriptions of the items present in both runtimes that changed.
+	pub changed: Vec<(String, String)>,
+}
+
+impl ItemsDiff {
+	fn new(old: &[Item], new: &[Item]) -> Self {
+		let find = |items: &[Item], name: &str| items.iter().find(|(n, _)| n == name).map(|(_, d)| d.clone());
+		ItemsDiff {
+			added: new.iter().filter(|(n, _)| find(old, n).is_none()).map(|(_, d)| d.clone()).collect(),
+			removed: old.iter().filter(|(n, _)| find(new, n).is_none()).map(|(_, d)| d.clone()).collect(),
+			changed: old.iter()
+				.filter_map(|(n, o)| find(new, n).filter(|d| d != o).map(|d| (o.clone(), d)))
+				.collect(),
+		}
+	}
+
+	fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+	}
+}
+
+/// The changes to a module present in both runtimes.
+#[derive(Debug, Default)]
+pub struct ModuleDiff {
+	/// The name of the module.
+	pub name: String,
+	/// The old and new index of the module in `Call`, if it changed.
+	pub call_index: Option<(Option<u8>, Option<u8>)>,
+	/// Changes to the calls.
+	pub calls: ItemsDiff,
+	/// Changes to the events.
+	pub events: ItemsDiff,
+	/// Changes to the storage items.
+	pub storage: ItemsDiff,
+	/// Changes to the constants.
+	pub constants: ItemsDiff,
+	/// Changes to the errors.
+	pub errors: ItemsDiff,
+}
+
+impl ModuleDiff {
+	fn is_empty(&self) -> bool {
+		self.call_index.is_none() && self.calls.is_empty() && self.events.is_empty()
+			&& self.storage.is_empty() && self.constants.is_empty() && self.errors.is_empty()
+	}
+}
+
+/// The changes from one runtime's metadata to another's.
+#[derive(Debug, Default)]
+pub struct MetadataDiff {
+	/// Names of the new modules.
+	pub added_modules: Vec<String>,
+	/// Names of the removed modules.
+	pub removed_modules: Vec<String>,
+	/// Names of the removed modules that had calls, a subset of `removed_modules`.
+	pub removed_call_modules: Vec<String>,
+	/// Changes to the modules present in both runtimes.
+	pub changed_modules: Vec<ModuleDiff>,
+	/// The old and new signed extensions, if they changed.
+	pub signed_extensions: Option<(Vec<String>, Vec<String>)>,
+}
+
+impl MetadataDiff {
+	/// Whether the metadata of the two runtimes is equivalent.
+	pub fn is_empty(&self) -> bool {
+		self.added_modules.is_empty() && self.removed_modules.is_empty()
+			&& self.changed_modules.is_empty() && self.signed_extensions.is_none()
+	}
+
+	/// The changes that make transactions built for the old runtime invalid or mean something
+	/// else in the new one, which requires a `transaction_version` bump.
+	///
+	/// Adding calls at the end of a module or adding modules without shifting the index of
+	/// others is backwards compatible.
+	pub fn breaking_changes(&self) -> Vec<String> {
+		let mut changes = Vec::new();
+		for name in &self.removed_call_modules {
+			changes.push(format!("{} was removed together with its calls", name));
+		}
+		for module in &self.changed_modules {
+			if let Some((Some(old), new)) = module.call_index {
+				match new {
+					Some(new) => changes.push(format!("{} moved from call index {} to {}", module.name, old, new)),
+					None => changes.push(format!("{} no longer has calls", module.name)),
+				}
+			}
+			for call in &module.calls.removed {
+				changes.push(format!("{}: removed call {}", module.name, call));
+			}
+			for (old, new) in &module.calls.changed {
+				changes.push(format!("{}: call {} changed to {}", module.name, old, new));
+			}
+		}
+		if let Some((old, new)) = &self.signed_extensions {
+			changes.push(format!("Signed extensions changed from {:?} to {:?}", old, new));
+		}
+		changes
+	}
+}
+
+fn diff_module(old: &ModuleMetadata, old_index: Option<u8>, new: &ModuleMetadata, new_index: Option<u8>) -> ModuleDiff {
+	ModuleDiff {
+		name: module_name(new).into(),
+		call_index: if old_index != new_index { Some((old_index, new_index)) } else { None },
+		calls: ItemsDiff::new(&call_items(old), &call_items(new)),
+		events: ItemsDiff::new(&event_items(old), &event_items(new)),
+		storage: ItemsDiff::new(&storage_items(old), &storage_items(new)),
+		constants: ItemsDiff::new(&constant_items(old), &constant_items(new)),
+		errors: ItemsDiff::new(&error_items(old), &error_items(new)),
+	}
+}
+
+/// Compare the metadata of two runtimes.
+pub fn diff(old: &RuntimeMetadataPrefixed, new: &RuntimeMetadataPrefixed) -> Result<MetadataDiff, String> {
+	let (old, new) = (v11(old)?, v11(new)?);
+	let (old_modules, new_modules) = (decoded(&old.modules), decoded(&new.modules));
+	let find = |modules: &[ModuleMetadata], name: &str| modules.iter().any(|m| module_name(m) == name);
+	let index = |modules: &[ModuleMetadata], name: &str| call_modules(modules)
+		.find(|(_, m)| module_name(m) == name)
+		.map(|(index, _)| encoded_index(index))
+		.transpose();
+
+	let mut changed_modules = Vec::new();
+	for o in old_modules {
+		let name = module_name(o);
+		if let Some(n) = new_modules.iter().find(|n| module_name(n) == name) {
+			let diff = diff_module(o, index(old_modules, name)?, n, index(new_modules, name)?);
+			if !diff.is_empty() {
+				changed_modules.push(diff);
+			}
+		}
+	}
+
+	let signed_extensions = |metadata: &RuntimeMetadataV11| metadata.extrinsic.signed_extensions.iter()
+		.map(|extension| decoded(extension).clone())
+		.collect::<Vec<_>>();
+	let (old_extensions, new_extensions) = (signed_extensions(old), signed_extensions(new));
+
+	Ok(MetadataDiff {
+		added_modules: new_modules.iter()
+			.filter(|n| !find(old_modules, module_name(n)))
+			.map(|n| module_name(n).into())
+			.collect(),
+		removed_modules: old_modules.iter()
+			.filter(|o| !find(new_modules, module_name(o)))
+			.map(|o| module_name(o).into())
+			.collect(),
+		removed_call_modules: old_modules.iter()
+			.filter(|o| o.calls.is_some() && !find(new_modules, module_name(o)))
+			.map(|o| module_name(o).into())
+			.collect(),
+		changed_modules,
+		signed_extensions: if old_extensions != new_extensions {
+			Some((old_extensions, new_extensions))
+		} else {
+			None
+		},
+	})
+}
+
+/// Check that `new` bumps the `transaction_version` of `old` if the metadata diff has breaking
+/// changes, so wallets know their encoded transactions are no longer valid.
+pub fn check_transaction_version(
+	diff: &MetadataDiff,
+	old: &RuntimeVersion,
+	new: &RuntimeVersion,
+) -> Result<(), String> {
+	let breaking = diff.breaking_changes();
+	if breaking.is_empty() || new.transaction_version > old.transaction_version {
+		return Ok(());
+	}
+	Err(format!(
+		"The transaction_version must be increased above {} because of {} breaking change(s)",
+		old.transaction_version,
+		breaking.len(),
+	))
+}
+
+fn write_items(f: &mut fmt::Formatter, kind: &str, items: &ItemsDiff) -> fmt::Result {
+	for item in &items.added {
+		writeln!(f, "    + {} {}", kind, item)?;
+	}
+	for item in &items.removed {
+		writeln!(f, "    - {} {}", kind, item)?;
+	}
+	for (old, new) in &items.changed {
+		writeln!(f, "    ~ {} {} -> {}", kind, old, new)?;
+	}
+	Ok(())
+}
+
+impl fmt::Display for MetadataDiff {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.is_empty() {
+			return writeln!(f, "The metadata did not change.");
+		}
+		for name in &self.added_modules {
+			writeln!(f, "+ module {}", name)?;
+		}
+		for name in &self.removed_modules {
+			writeln!(f, "- module {}", name)?;
+		}
+		for module in &self.changed_modules {
+			writeln!(f, "~ module {}", module.name)?;
+			if let Some((old, new)) = module.call_index {
+				writeln!(f, "    ~ call index {:?} -> {:?}", old, new)?;
+			}
+			write_items(f, "call", &module.calls)?;
+			write_items(f, "event", &module.events)?;
+			write_items(f, "storage", &module.storage)?;
+			write_items(f, "constant", &module.constants)?;
+			write_items(f, "error", &module.errors)?;
+		}
+		if let Some((old, new)) = &self.signed_extensions {
+			writeln!(f, "~ signed extensions {:?} -> {:?}", old, new)?;
+		}
+
+		let breaking = self.breaking_changes();
+		if !breaking.is_empty() {
+			writeln!(f, "Changes that require a transaction_version bump:")?;
+			for change in breaking {
+				writeln!(f, "    {}", change)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_metadata::{ExtrinsicMetadata, FunctionArgumentMetadata, META_RESERVED};
+
+	fn call(name: &str, arguments: &[(&str, &str)]) -> FunctionMetadata {
+		FunctionMetadata {
+			name: DecodeDifferent::Decoded(name.into()),
+			arguments: DecodeDifferent::Decoded(arguments.iter()
+				.map(|(name, ty)| FunctionArgumentMetadata {
+					name: DecodeDifferent::Decoded((*name).into()),
+					ty: DecodeDifferent::Decoded((*ty).into()),
+				})
+				.collect()),
+			documentation: DecodeDifferent::Decoded(vec![]),
+		}
+	}
+
+	fn module(name: &str, calls: Option<Vec<FunctionMetadata>>) -> ModuleMetadata {
+		ModuleMetadata {
+			name: DecodeDifferent::Decoded(name.into()),
+			storage: None,
+			calls: calls.map(DecodeDifferent::Decoded),
+			event: None,
+			constants: DecodeDifferent::Decoded(vec![]),
+			errors: DecodeDifferent::Decoded(vec![]),
+		}
+	}
+
+	fn metadata(modules: Vec<ModuleMetadata>, signed_extensions: &[&str]) -> RuntimeMetadataPrefixed {
+		RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V11(RuntimeMetadataV11 {
+			modules: DecodeDifferent::Decoded(modules),
+			extrinsic: ExtrinsicMetadata {
+				version: 4,
+				signed_extensions: signed_extensions.iter()
+					.map(|extension| DecodeDifferent::Decoded((*extension).into()))
+					.collect(),
+			},
+		}))
+	}
+
+	fn system() -> ModuleMetadata {
+		module("System", Some(vec![call("remark", &[("_remark", "Vec<u8>")])]))
+	}
+
+	fn balances() -> ModuleMetadata {
+		module("Balances", Some(vec![call("transfer", &[("dest", "LookupSource"), ("value", "Balance")])]))
+	}
+
+	fn template() -> ModuleMetadata {
+		module("TemplateModule", Some(vec![call("do_something", &[("something", "u32")])]))
+	}
+
+	/// System, Aura without calls, Balances and TemplateModule, with a single signed extension.
+	fn runtime() -> RuntimeMetadataPrefixed {
+		metadata(vec![system(), module("Aura", None), balances(), template()], &["CheckNonce"])
+	}
+
+	fn version(transaction_version: u32) -> RuntimeVersion {
+		RuntimeVersion { transaction_version, ..Default::default() }
+	}
+
+	#[test]
+	fn call_indices_skip_modules_without_calls() {
+		let runtime = runtime();
+		assert_eq!(call_index(&runtime, "System", "remark"), Ok([0, 0]));
+		assert_eq!(call_index(&runtime, "TemplateModule", "do_something"), Ok([2, 0]));
+		assert!(call_index(&runtime, "Aura", "remark").is_err());
+		assert!(call_index(&runtime, "Balances", "remark").is_err());
+	}
+
+	#[test]
+	fn equal_metadata_has_no_changes() {
+		let diff = diff(&runtime(), &runtime()).unwrap();
+		assert!(diff.is_empty());
+		assert!(diff.breaking_changes().is_empty());
+		assert_eq!(check_transaction_version(&diff, &version(1), &version(1)), Ok(()));
+	}
+
+	#[test]
+	fn appending_calls_and_modules_is_not_breaking() {
+		let template = module("TemplateModule", Some(vec![
+			call("do_something", &[("something", "u32")]),
+			call("bind_asset", &[("asset", "AssetId")]),
+		]));
+		let assets = module("Assets", Some(vec![call("issue", &[("total", "Balance")])]));
+		let new = metadata(
+			vec![system(), module("Aura", None), balances(), template, assets],
+			&["CheckNonce"],
+		);
+
+		let diff = diff(&runtime(), &new).unwrap();
+		assert_eq!(diff.added_modules, vec!["Assets".to_string()]);
+		assert_eq!(diff.changed_modules.len(), 1);
+		assert_eq!(diff.changed_modules[0].calls.added, vec!["#1 bind_asset(asset: AssetId)".to_string()]);
+		assert!(diff.breaking_changes().is_empty());
+		assert_eq!(check_transaction_version(&diff, &version(1), &version(1)), Ok(()));
+	}
+
+	#[test]
+	fn changing_and_removing_calls_is_breaking() {
+		let new = metadata(vec![
+			module("System", Some(vec![])),
+			module("Aura", None),
+			module("Balances", Some(vec![call("transfer", &[("dest", "AccountId"), ("value", "Balance")])])),
+			template(),
+		], &["CheckNonce"]);
+
+		let diff = diff(&runtime(), &new).unwrap();
+		assert_eq!(diff.breaking_changes(), vec![
+			"System: removed call #0 remark(_remark: Vec<u8>)".to_string(),
+			"Balances: call #0 transfer(dest: LookupSource, value: Balance) changed to \
+				#0 transfer(dest: AccountId, value: Balance)".to_string(),
+		]);
+		assert!(check_transaction_version(&diff, &version(1), &version(1)).is_err());
+		assert_eq!(check_transaction_version(&diff, &version(1), &version(2)), Ok(()));
+	}
+
+	#[test]
+	fn removing_a_module_with_calls_is_breaking() {
+		// The last module with calls, so no other module moves.
+		let new = metadata(vec![system(), module("Aura", None), balances()], &["CheckNonce"]);
+
+		let diff = diff(&runtime(), &new).unwrap();
+		assert_eq!(diff.removed_modules, vec!["TemplateModule".to_string()]);
+		assert_eq!(diff.breaking_changes(), vec![
+			"TemplateModule was removed together with its calls".to_string(),
+		]);
+		assert!(check_transaction_version(&diff, &version(1), &version(1)).is_err());
+	}
+
+	#[test]
+	fn removing_a_module_without_calls_is_not_breaking() {
+		let new = metadata(vec![system(), balances(), template()], &["CheckNonce"]);
+
+		let diff = diff(&runtime(), &new).unwrap();
+		assert_eq!(diff.removed_modules, vec!["Aura".to_string()]);
+		assert!(diff.breaking_changes().is_empty());
+	}
+
+	#[test]
+	fn shifting_call_indices_is_breaking() {
+		let new = metadata(vec![
+			system(),
+			module("Aura", None),
+			module("Sudo", Some(vec![call("sudo", &[("call", "Box<Call>")])])),
+			balances(),
+			template(),
+		], &["CheckNonce"]);
+
+		let diff = diff(&runtime(), &new).unwrap();
+		assert_eq!(diff.added_modules, vec!["Sudo".to_string()]);
+		assert_eq!(diff.breaking_changes(), vec![
+			"Balances moved from call index 1 to 2".to_string(),
+			"TemplateModule moved from call index 2 to 3".to_string(),
+		]);
+	}
+
+	#[test]
+	fn changing_signed_extensions_is_breaking() {
+		let new = metadata(
+			vec![system(), module("Aura", None), balances(), template()],
+			&["CheckNonce", "ChargeTransactionPayment"],
+		);
+
+		let diff = diff(&runtime(), &new).unwrap();
+		assert_eq!(diff.breaking_changes().len(), 1);
+		assert!(check_transaction_version(&diff, &version(3), &version(3)).is_err());
+		assert_eq!(check_transaction_version(&diff, &version(3), &version(4)), Ok(()));
+	}
+}
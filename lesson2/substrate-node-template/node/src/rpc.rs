@@ -0,0 +1,43 @@
+//! A collection of node-specific RPC methods.
+//!
+//! Substrate provides the `sc-rpc` crate, which defines the core RPC layer used by Substrate
+//! nodes. This file extends those RPC definitions with capabilities that are specific to this
+//! project's runtime configuration.
+
+use std::sync::Arc;
+
+use node_template_runtime::{opaque::Block, AccountId, Balance, BlockNumber};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+
+/// Full client dependencies.
+pub struct FullDeps<C> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+}
+
+/// Instantiate all full RPC extensions.
+pub fn create_full<C, M>(deps: FullDeps<C>) -> jsonrpc_core::IoHandler<M> where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+	C::Api: node_template_runtime::EvmRuntimeApi<Block>,
+	M: jsonrpc_core::Metadata + Default,
+{
+	use pallet_contracts_rpc::{Contracts, ContractsApi};
+	use crate::evm_rpc::{Evm, EvmApi};
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	let FullDeps { client } = deps;
+
+	// Making synchronous calls in light client freezes the browser currently,
+	// more context: https://github.com/paritytech/substrate/pull/3480
+	// These RPCs should use an asynchronous caller instead.
+	io.extend_with(
+		ContractsApi::to_delegate(Contracts::new(client.clone()))
+	);
+	io.extend_with(
+		EvmApi::to_delegate(Evm::new(client))
+	);
+
+	io
+}
@@ -1,5 +1,6 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use sc_client_api::ExecutorProvider;
@@ -9,7 +10,14 @@ use sc_service::{error::{Error as ServiceError}, AbstractService, Configuration,
 use sp_inherents::InherentDataProviders;
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
+#[cfg(not(feature = "babe-consensus"))]
 use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair};
+#[cfg(not(feature = "babe-consensus"))]
+use sc_service::config::KeystoreConfig;
+#[cfg(not(feature = "babe-consensus"))]
+use crate::signing_guard::SigningGuard;
+#[cfg(not(feature = "babe-consensus"))]
+use crate::slashing_protection::SlashingProtection;
 use sc_finality_grandpa::{
 	FinalityProofProvider as GrandpaFinalityProofProvider, StorageAndProofProvider, SharedVoterState,
 };
@@ -28,6 +36,7 @@ native_executor_instance!(
 macro_rules! new_full_start {
 	($config:expr) => {{
 		use std::sync::Arc;
+		#[cfg(not(feature = "babe-consensus"))]
 		use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
 
 		let mut import_setup = None;
@@ -60,24 +69,62 @@ macro_rules! new_full_start {
 					select_chain,
 				)?;
 
-				let aura_block_import = sc_consensus_aura::AuraBlockImport::<_, _, _, AuraPair>::new(
-					grandpa_block_import.clone(), client.clone(),
-				);
+				#[cfg(not(feature = "babe-consensus"))]
+				let import_queue = {
+					let aura_block_import = sc_consensus_aura::AuraBlockImport::<_, _, _, AuraPair>::new(
+						grandpa_block_import.clone(), client.clone(),
+					);
 
-				let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _>(
-					sc_consensus_aura::slot_duration(&*client)?,
-					aura_block_import,
-					Some(Box::new(grandpa_block_import.clone())),
-					None,
-					client,
-					inherent_data_providers.clone(),
-					spawn_task_handle,
-					registry,
-				)?;
+					let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _>(
+						sc_consensus_aura::slot_duration(&*client)?,
+						aura_block_import,
+						Some(Box::new(grandpa_block_import.clone())),
+						None,
+						client,
+						inherent_data_providers.clone(),
+						spawn_task_handle,
+						registry,
+					)?;
+
+					import_setup = Some((grandpa_block_import, grandpa_link));
+
+					import_queue
+				};
+
+				#[cfg(feature = "babe-consensus")]
+				let import_queue = {
+					let justification_import = grandpa_block_import.clone();
+
+					let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
+						sc_consensus_babe::Config::get_or_compute(&*client)?,
+						grandpa_block_import,
+						client.clone(),
+					)?;
+
+					let import_queue = sc_consensus_babe::import_queue(
+						babe_link.clone(),
+						babe_block_import.clone(),
+						Some(Box::new(justification_import)),
+						None,
+						client,
+						inherent_data_providers.clone(),
+						spawn_task_handle,
+						registry,
+					)?;
 
-				import_setup = Some((grandpa_block_import, grandpa_link));
+					import_setup = Some((babe_block_import, grandpa_link, babe_link));
+
+					import_queue
+				};
 
 				Ok(import_queue)
+			})?
+			.with_rpc_extensions(|builder| -> Result<jsonrpc_core::IoHandler<sc_rpc::Metadata>, _> {
+				let deps = crate::rpc::FullDeps {
+					client: builder.client().clone(),
+				};
+
+				Ok(crate::rpc::create_full(deps))
 			})?;
 
 		(builder, import_setup, inherent_data_providers)
@@ -85,7 +132,23 @@ macro_rules! new_full_start {
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(config: Configuration) -> Result<impl AbstractService, ServiceError> {
+///
+/// Authored blocks are recorded in the slashing protection directory before they are signed,
+/// which defaults to `slashing-protection` next to the keystore. BABE authoring is not
+/// protected.
+#[cfg_attr(feature = "babe-consensus", allow(unused_variables))]
+pub fn new_full(
+	config: Configuration,
+	slashing_protection: Option<PathBuf>,
+) -> Result<impl AbstractService, ServiceError> {
+	crate::wasm::check_wasm_binary(&config)?;
+
+	#[cfg(not(feature = "babe-consensus"))]
+	let slashing_protection = slashing_protection
+		.or_else(|| match &config.keystore {
+			KeystoreConfig::Path { path, .. } => path.parent().map(|dir| dir.join("slashing-protection")),
+			_ => None,
+		});
 	let role = config.role.clone();
 	let force_authoring = config.force_authoring;
 	let name = config.network.node_name.clone();
@@ -93,9 +156,14 @@ pub fn new_full(config: Configuration) -> Result<impl AbstractService, ServiceEr
 
 	let (builder, mut import_setup, inherent_data_providers) = new_full_start!(config);
 
+	#[cfg(not(feature = "babe-consensus"))]
 	let (block_import, grandpa_link) =
 		import_setup.take()
 			.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
+	#[cfg(feature = "babe-consensus")]
+	let (block_import, grandpa_link, babe_link) =
+		import_setup.take()
+			.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
 
 	let service = builder
 		.with_finality_proof_provider(|client, backend| {
@@ -119,22 +187,54 @@ pub fn new_full(config: Configuration) -> Result<impl AbstractService, ServiceEr
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
-		let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _>(
-			sc_consensus_aura::slot_duration(&*client)?,
-			client,
-			select_chain,
-			block_import,
-			proposer,
-			service.network(),
-			inherent_data_providers.clone(),
-			force_authoring,
-			service.keystore(),
-			can_author_with,
-		)?;
+		#[cfg(not(feature = "babe-consensus"))]
+		{
+			let guard = slashing_protection
+				.ok_or_else(|| ServiceError::Other("An authority needs a slashing protection directory".into()))
+				.and_then(|dir| SigningGuard::open(&dir).map_err(|e| ServiceError::Other(
+					format!("Failed to open the slashing protection record at {}: {}", dir.display(), e),
+				)))?;
+			let proposer = SlashingProtection::new(proposer, client.clone(), guard);
+
+			let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _>(
+				sc_consensus_aura::slot_duration(&*client)?,
+				client,
+				select_chain,
+				block_import,
+				proposer,
+				service.network(),
+				inherent_data_providers.clone(),
+				force_authoring,
+				service.keystore(),
+				can_author_with,
+			)?;
+
+			// the AURA authoring task is considered essential, i.e. if it
+			// fails we take down the service with it.
+			service.spawn_essential_task("aura", aura);
+		}
+
+		#[cfg(feature = "babe-consensus")]
+		{
+			let babe_config = sc_consensus_babe::BabeParams {
+				keystore: service.keystore(),
+				client,
+				select_chain,
+				env: proposer,
+				block_import,
+				sync_oracle: service.network(),
+				inherent_data_providers: inherent_data_providers.clone(),
+				force_authoring,
+				babe_link,
+				can_author_with,
+			};
+
+			let babe = sc_consensus_babe::start_babe(babe_config)?;
 
-		// the AURA authoring task is considered essential, i.e. if it
-		// fails we take down the service with it.
-		service.spawn_essential_task("aura", aura);
+			// the BABE authoring task is considered essential, i.e. if it
+			// fails we take down the service with it.
+			service.spawn_essential_task("babe-proposer", babe);
+		}
 	}
 
 	// if the node isn't actively participating in consensus then it doesn't
@@ -193,6 +293,8 @@ pub fn new_full(config: Configuration) -> Result<impl AbstractService, ServiceEr
 
 /// Builds a new service for a light client.
 pub fn new_light(config: Configuration) -> Result<impl AbstractService, ServiceError> {
+	crate::wasm::check_wasm_binary(&config)?;
+
 	let inherent_data_providers = InherentDataProviders::new();
 
 	ServiceBuilder::new_light::<Block, RuntimeApi, Executor>(config)?
@@ -232,6 +334,7 @@ pub fn new_light(config: Configuration) -> Result<impl AbstractService, ServiceE
 			let finality_proof_request_builder =
 				finality_proof_import.create_finality_proof_request_builder();
 
+			#[cfg(not(feature = "babe-consensus"))]
 			let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair, _>(
 				sc_consensus_aura::slot_duration(&*client)?,
 				grandpa_block_import,
@@ -243,6 +346,26 @@ pub fn new_light(config: Configuration) -> Result<impl AbstractService, ServiceE
 				prometheus_registry,
 			)?;
 
+			#[cfg(feature = "babe-consensus")]
+			let import_queue = {
+				let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
+					sc_consensus_babe::Config::get_or_compute(&*client)?,
+					grandpa_block_import,
+					client.clone(),
+				)?;
+
+				sc_consensus_babe::import_queue(
+					babe_link,
+					babe_block_import,
+					None,
+					Some(Box::new(finality_proof_import)),
+					client,
+					inherent_data_providers.clone(),
+					spawn_task_handle,
+					prometheus_registry,
+				)?
+			};
+
 			Ok((import_queue, finality_proof_request_builder))
 		})?
 		.with_finality_proof_provider(|client, backend| {
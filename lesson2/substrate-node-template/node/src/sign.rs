@@ -0,0 +1,159 @@
+//! The `sign-transaction` subcommand, which builds and signs an extrinsic without a node.
+
+use std::{fs, path::PathBuf};
+
+use codec::{Decode, Encode};
+use node_template_runtime::{
+	template, AccountId, Balance, BlockNumber, Call, Hash, Index, SignedExtra, UncheckedExtrinsic,
+	VERSION,
+};
+use sp_core::{crypto::Pair as _, ed25519, hexdisplay::HexDisplay, sr25519};
+use sp_runtime::{
+	generic::{Era, SignedPayload},
+	traits::IdentifyAccount,
+	MultiSignature, MultiSigner,
+};
+use structopt::StructOpt;
+
+/// Build a signed `UncheckedExtrinsic` for a call and print it hex-encoded, ready to be
+/// submitted with `author_submitExtrinsic`.
+///
+/// Everything the signature commits to is given on the command line, so no connection to a
+/// node is needed and the command can run on an air-gapped machine.
+#[derive(Debug, StructOpt)]
+pub struct SignTransactionCmd {
+	/// The hex-encoded `Call` to sign, as printed by e.g. `upgrade-check`.
+	#[structopt(long, required_unless = "call_file")]
+	pub call: Option<String>,
+
+	/// Read the hex-encoded `Call` from this file. Use it for calls too long for an argument,
+	/// like `sudo(set_code)`.
+	#[structopt(long, parse(from_os_str), conflicts_with = "call")]
+	pub call_file: Option<PathBuf>,
+
+	/// The secret key URI of the signer, e.g. a mnemonic or `//Alice`.
+	#[structopt(long)]
+	pub suri: String,
+
+	/// The password of the secret key URI.
+	#[structopt(long)]
+	pub password: Option<String>,
+
+	/// The signature scheme of the key: `sr25519` or `ed25519`.
+	#[structopt(long, default_value = "sr25519")]
+	pub scheme: String,
+
+	/// The nonce of the signing account.
+	#[structopt(long)]
+	pub nonce: Index,
+
+	/// The tip for the block author, in the smallest unit.
+	#[structopt(long, default_value = "0")]
+	pub tip: Balance,
+
+	/// The hash of the chain's genesis block.
+	#[structopt(long)]
+	pub genesis: String,
+
+	/// Make the transaction mortal for this many blocks, starting at `--era-block-number`.
+	/// Without it the transaction is immortal.
+	#[structopt(long, requires_all = &["era_block_number", "era_block_hash"])]
+	pub era_period: Option<u64>,
+
+	/// The number of the block the mortal era starts at.
+	#[structopt(long)]
+	pub era_block_number: Option<BlockNumber>,
+
+	/// The hash of the block the mortal era starts at.
+	#[structopt(long)]
+	pub era_block_hash: Option<String>,
+
+	/// The `spec_version` of the runtime the transaction is for. Defaults to the runtime this
+	/// node was built with.
+	#[structopt(long)]
+	pub spec_version: Option<u32>,
+
+	/// The `transaction_version` of the runtime the transaction is for. Defaults to the runtime
+	/// this node was built with.
+	#[structopt(long)]
+	pub transaction_version: Option<u32>,
+}
+
+fn parse_hash(hash: &str) -> sc_cli::Result<Hash> {
+	hash.trim_start_matches("0x").parse()
+		.map_err(|e| sc_cli::Error::Input(format!("Invalid hash {}: {:?}", hash, e)))
+}
+
+fn parse_hex(hex: &str) -> sc_cli::Result<Vec<u8>> {
+	let hex = hex.trim();
+	let hex = hex.trim_start_matches("0x");
+	if hex.len() % 2 != 0 {
+		return Err(sc_cli::Error::Input("Odd number of hex digits".into()));
+	}
+	(0..hex.len()).step_by(2)
+		.map(|i| u8::from_str_radix(&hex[i..i + 2], 16)
+			.map_err(|e| sc_cli::Error::Input(format!("Invalid hex: {}", e))))
+		.collect()
+}
+
+impl SignTransactionCmd {
+	/// Run the command.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let call = match (&self.call, &self.call_file) {
+			(Some(call), _) => parse_hex(call)?,
+			(None, Some(path)) => parse_hex(&fs::read_to_string(path)?)?,
+			(None, None) => return Err(sc_cli::Error::Input("Either --call or --call-file is required".into())),
+		};
+		let call = Call::decode(&mut &call[..])
+			.map_err(|e| sc_cli::Error::Input(format!("Invalid call: {}", e.what())))?;
+
+		let genesis = parse_hash(&self.genesis)?;
+		let (era, era_block_hash) = match (self.era_period, self.era_block_number, &self.era_block_hash) {
+			(Some(period), Some(number), Some(hash)) => (Era::mortal(period, number.into()), parse_hash(hash)?),
+			_ => (Era::Immortal, genesis),
+		};
+
+		let extra: SignedExtra = (
+			frame_system::CheckSpecVersion::new(),
+			frame_system::CheckTxVersion::new(),
+			frame_system::CheckGenesis::new(),
+			frame_system::CheckEra::from(era),
+			frame_system::CheckNonce::from(self.nonce),
+			frame_system::CheckWeight::new(),
+			template::FreeFirstCall::from(self.tip),
+		);
+		// What the extensions add to the signed payload without it being part of the extrinsic.
+		let additional_signed = (
+			self.spec_version.unwrap_or(VERSION.spec_version),
+			self.transaction_version.unwrap_or(VERSION.transaction_version),
+			genesis,
+			era_block_hash,
+			(),
+			(),
+			(),
+		);
+		let payload = SignedPayload::from_raw(call, extra, additional_signed);
+
+		let (signer, signature): (MultiSigner, MultiSignature) = match self.scheme.as_str() {
+			"sr25519" => {
+				let pair = sr25519::Pair::from_string(&self.suri, self.password.as_deref())
+					.map_err(|e| sc_cli::Error::Input(format!("Invalid secret key URI: {:?}", e)))?;
+				(pair.public().into(), payload.using_encoded(|payload| pair.sign(payload)).into())
+			},
+			"ed25519" => {
+				let pair = ed25519::Pair::from_string(&self.suri, self.password.as_deref())
+					.map_err(|e| sc_cli::Error::Input(format!("Invalid secret key URI: {:?}", e)))?;
+				(pair.public().into(), payload.using_encoded(|payload| pair.sign(payload)).into())
+			},
+			scheme => return Err(sc_cli::Error::Input(format!("Unsupported signature scheme {}", scheme))),
+		};
+		let account: AccountId = signer.into_account();
+
+		let (call, extra, _) = payload.deconstruct();
+		let extrinsic = UncheckedExtrinsic::new_signed(call, account.clone(), signature, extra);
+
+		eprintln!("Signed by {} with nonce {}.", account, self.nonce);
+		println!("0x{}", HexDisplay::from(&extrinsic.encode()));
+		Ok(())
+	}
+}
@@ -0,0 +1,196 @@
+//! The slashing-protection record of everything a key has signed.
+
+use std::{
+	fmt, fs, io::{self, Write}, os::unix::io::AsRawFd, path::{Path, PathBuf},
+};
+
+use sp_core::{blake2_256, hexdisplay::HexDisplay};
+
+/// A persistent record of the highest position, e.g. slot, each key signed a payload at.
+///
+/// The watermark of a sequence is kept in `<root>/<public key>/<name>`, holding the position
+/// and the hash of the payload signed at it, and survives restarts. The key's directory is
+/// locked with `flock` while the watermark is checked and replaced, so of several processes
+/// sharing the record, only one signs at a position and the watermark never moves back.
+/// Several nodes sharing a key must share the directory, on a file system whose locks all of
+/// them see.
+#[derive(Debug, Clone)]
+pub struct SigningGuard {
+	root: PathBuf,
+}
+
+/// Why a payload must not be signed.
+#[derive(Debug)]
+pub enum GuardError {
+	/// A different payload was already signed at the position, or one at a later position.
+	Equivocation(String),
+	/// The record could not be read or written.
+	Io(io::Error),
+}
+
+impl fmt::Display for GuardError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			GuardError::Equivocation(context) =>
+				write!(f, "Refusing to sign a second payload for {}", context),
+			GuardError::Io(e) => write!(f, "Slashing protection record failed: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for GuardError {}
+
+impl From<io::Error> for GuardError {
+	fn from(e: io::Error) -> Self {
+		GuardError::Io(e)
+	}
+}
+
+impl SigningGuard {
+	/// Open the record in `root`, creating the directory if needed.
+	pub fn open<P: AsRef<Path>>(root: P) -> io::Result<Self> {
+		fs::create_dir_all(root.as_ref())?;
+		Ok(SigningGuard { root: root.as_ref().to_path_buf() })
+	}
+
+	fn key_dir(&self, public: &[u8]) -> io::Result<PathBuf> {
+		let dir = self.root.join(format!("{}", HexDisplay::from(&public)));
+		fs::create_dir_all(&dir)?;
+		Ok(dir)
+	}
+
+	/// Record that `public` signs `payload` at `position` of the sequence `name`, e.g. a block
+	/// in a slot.
+	///
+	/// The same payload at the highest position signed can be signed again, so a request can
+	/// be retried, but a different payload at it or any payload below it is refused without
+	/// recording anything.
+	pub fn record_watermark(
+		&self,
+		public: &[u8],
+		name: &str,
+		position: u64,
+		payload: &[u8],
+	) -> Result<(), GuardError> {
+		let dir = self.key_dir(public)?;
+		let path = dir.join(name);
+		let hash = blake2_256(payload);
+
+		let _lock = lock(&dir)?;
+		match read_watermark(&path)? {
+			Some((highest, _)) if position < highest =>
+				Err(GuardError::Equivocation(format!("{}-{}", name, position))),
+			Some((highest, highest_hash)) if position == highest => if highest_hash == hash {
+				Ok(())
+			} else {
+				Err(GuardError::Equivocation(format!("{}-{}", name, position)))
+			},
+			_ => Ok(write_watermark(&dir, name, position, &hash)?),
+		}
+	}
+}
+
+/// Take an exclusive lock on `dir`, which is held until the returned file is dropped.
+fn lock(dir: &Path) -> io::Result<fs::File> {
+	let file = fs::File::open(dir)?;
+	// SAFETY: `flock` only operates on the open file descriptor of `file`.
+	if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(file)
+}
+
+/// Read the highest position and payload hash recorded at `path`, if any.
+fn read_watermark(path: &Path) -> io::Result<Option<(u64, [u8; 32])>> {
+	let bytes = match fs::read(path) {
+		Ok(bytes) => bytes,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+		Err(e) => return Err(e),
+	};
+	if bytes.len() != 40 {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Corrupt watermark {}", path.display())));
+	}
+	let mut position = [0u8; 8];
+	let mut hash = [0u8; 32];
+	position.copy_from_slice(&bytes[..8]);
+	hash.copy_from_slice(&bytes[8..]);
+	Ok(Some((u64::from_le_bytes(position), hash)))
+}
+
+/// Replace the watermark `name` in `dir`. It is renamed into place, so a crash never leaves a
+/// partial write.
+fn write_watermark(dir: &Path, name: &str, position: u64, hash: &[u8; 32]) -> io::Result<()> {
+	let temp = dir.join(format!(".{}-{}", name, std::process::id()));
+	let mut file = fs::File::create(&temp)?;
+	file.write_all(&position.to_le_bytes())?;
+	file.write_all(hash)?;
+	file.sync_all()?;
+	fs::rename(&temp, dir.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::thread;
+
+	#[test]
+	fn watermark_refuses_lower_and_conflicting_positions() {
+		let dir = tempfile::tempdir().unwrap();
+		let guard = SigningGuard::open(dir.path()).unwrap();
+
+		assert!(guard.record_watermark(b"alice", "slot", 5, b"block").is_ok());
+		assert!(guard.record_watermark(b"alice", "slot", 5, b"block").is_ok());
+		assert!(matches!(
+			guard.record_watermark(b"alice", "slot", 5, b"other block"),
+			Err(GuardError::Equivocation(_))
+		));
+		assert!(guard.record_watermark(b"alice", "slot", 7, b"later block").is_ok());
+		// Slot 6 was never signed, but is below the watermark now.
+		assert!(matches!(
+			guard.record_watermark(b"alice", "slot", 6, b"block"),
+			Err(GuardError::Equivocation(_))
+		));
+		// Other keys are not affected.
+		assert!(guard.record_watermark(b"bob", "slot", 6, b"block").is_ok());
+	}
+
+	#[test]
+	fn watermark_survives_reopening() {
+		let dir = tempfile::tempdir().unwrap();
+		SigningGuard::open(dir.path()).unwrap().record_watermark(b"alice", "slot", 1, b"block").unwrap();
+
+		let guard = SigningGuard::open(dir.path()).unwrap();
+		assert!(guard.record_watermark(b"alice", "slot", 1, b"other block").is_err());
+	}
+
+	#[test]
+	fn concurrent_signers_never_move_the_watermark_back() {
+		let dir = tempfile::tempdir().unwrap();
+		let signers: Vec<_> = (1..=20u64)
+			.map(|slot| {
+				let guard = SigningGuard::open(dir.path()).unwrap();
+				thread::spawn(move || guard.record_watermark(b"alice", "slot", slot, &slot.to_le_bytes()).is_ok())
+			})
+			.collect();
+		let signed = signers.into_iter().map(|signer| signer.join().unwrap()).filter(|&signed| signed).count();
+
+		assert!(signed >= 1);
+		let key_dir = dir.path().join(format!("{}", HexDisplay::from(&&b"alice"[..])));
+		let (highest, _) = read_watermark(&key_dir.join("slot")).unwrap().unwrap();
+		assert_eq!(highest, 20);
+	}
+
+	#[test]
+	fn only_one_of_concurrent_signers_signs_a_position() {
+		let dir = tempfile::tempdir().unwrap();
+		let signers: Vec<_> = (0..10u8)
+			.map(|block| {
+				let guard = SigningGuard::open(dir.path()).unwrap();
+				thread::spawn(move || guard.record_watermark(b"alice", "slot", 3, &[block]).is_ok())
+			})
+			.collect();
+
+		let signed = signers.into_iter().map(|signer| signer.join().unwrap()).filter(|&signed| signed).count();
+		assert_eq!(signed, 1);
+	}
+}
@@ -0,0 +1,206 @@
+//! Protection against authoring two blocks for the same Aura slot.
+
+use std::{marker::PhantomData, pin::Pin, sync::Arc, time::Duration};
+
+use codec::Decode;
+use futures::{future, Future, TryFutureExt};
+use sp_api::ProvideRuntimeApi;
+use sp_consensus::{
+	Environment, Error as ConsensusError, Proposal, Proposer, RecordProof,
+};
+use sp_consensus_aura::{sr25519::AuthorityId as AuraId, AuraApi, AURA_ENGINE_ID};
+use sp_inherents::InherentData;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, DigestFor, Header as HeaderT},
+};
+
+use crate::signing_guard::SigningGuard;
+
+/// Proposer factory for the blocks this node authors that records the highest slot each key
+/// authored in and the block for it in a `SigningGuard`, and refuses a different block for that
+/// slot or any block for an earlier one.
+///
+/// The Aura worker signs the hash of the header it gets from the proposer. The block is
+/// recorded when its proposal is ready, so a refused block fails the proposal and is never
+/// signed.
+///
+/// As the record is kept per authority key, nodes of a failover pair that share a key must
+/// share the record's directory.
+pub struct SlashingProtection<B, E, C> {
+	inner: E,
+	client: Arc<C>,
+	guard: SigningGuard,
+	_phantom: PhantomData<B>,
+}
+
+impl<B, E, C> SlashingProtection<B, E, C> {
+	/// Wrap `inner`, recording the authored slots in `guard`.
+	pub fn new(inner: E, client: Arc<C>, guard: SigningGuard) -> Self {
+		SlashingProtection { inner, client, guard, _phantom: PhantomData }
+	}
+}
+
+impl<B, E, C> Environment<B> for SlashingProtection<B, E, C> where
+	B: BlockT,
+	E: Environment<B>,
+	E::Error: Send,
+	<E::Proposer as Proposer<B>>::Error: Send,
+	C: ProvideRuntimeApi<B> + Send + Sync + 'static,
+	C::Api: AuraApi<B, AuraId>,
+{
+	type Proposer = ProtectedProposer<B, E::Proposer, C>;
+	type CreateProposer = Pin<Box<dyn Future<Output = Result<Self::Proposer, E::Error>> + Send>>;
+	type Error = E::Error;
+
+	fn init(&mut self, parent_header: &B::Header) -> Self::CreateProposer {
+		let client = self.client.clone();
+		let guard = self.guard.clone();
+		Box::pin(self.inner.init(parent_header).map_ok(move |inner| {
+			ProtectedProposer { inner, client, guard, _phantom: PhantomData }
+		}))
+	}
+}
+
+/// A proposer that records the blocks it proposes, see `SlashingProtection`.
+pub struct ProtectedProposer<B, P, C> {
+	inner: P,
+	client: Arc<C>,
+	guard: SigningGuard,
+	_phantom: PhantomData<B>,
+}
+
+impl<B, P, C> Proposer<B> for ProtectedProposer<B, P, C> where
+	B: BlockT,
+	P: Proposer<B>,
+	P::Error: Send,
+	C: ProvideRuntimeApi<B> + Send + Sync + 'static,
+	C::Api: AuraApi<B, AuraId>,
+{
+	type Error = P::Error;
+	type Transaction = P::Transaction;
+	type Proposal = Pin<Box<dyn Future<Output = Result<Proposal<B, P::Transaction>, P::Error>> + Send>>;
+
+	fn propose(
+		&mut self,
+		inherent_data: InherentData,
+		inherent_digests: DigestFor<B>,
+		max_duration: Duration,
+		record_proof: RecordProof,
+	) -> Self::Proposal {
+		let client = self.client.clone();
+		let guard = self.guard.clone();
+		Box::pin(self.inner.propose(inherent_data, inherent_digests, max_duration, record_proof)
+			.and_then(move |proposal| {
+				let header = proposal.block.header();
+				if let Err(e) = record(&*client, &guard, header) {
+					log::error!(target: "slashing-protection", "Not signing block #{}: {}", header.number(), e);
+					return future::err(e.into());
+				}
+				future::ok(proposal)
+			}))
+	}
+}
+
+/// Record the block with header `header`, which is not sealed yet, for its slot.
+fn record<B, C>(client: &C, guard: &SigningGuard, header: &B::Header) -> Result<(), ConsensusError> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: AuraApi<B, AuraId>,
+{
+	let slot = header.digest().logs().iter()
+		.filter_map(|item| item.as_pre_runtime())
+		.find(|(id, _)| *id == AURA_ENGINE_ID)
+		.and_then(|(_, data)| u64::decode(&mut &data[..]).ok())
+		.ok_or_else(|| ConsensusError::ClientImport("Authored block has no Aura slot".into()))?;
+
+	// The author of a slot is determined by the authorities at the parent block.
+	let authorities = client.runtime_api()
+		.authorities(&BlockId::Hash(*header.parent_hash()))
+		.map_err(|e| ConsensusError::ClientImport(format!("Failed to get the Aura authorities: {:?}", e)))?;
+	if authorities.is_empty() {
+		return Err(ConsensusError::ClientImport("No Aura authorities".into()));
+	}
+	let author = &authorities[(slot % authorities.len() as u64) as usize];
+
+	guard.record_watermark(author.as_ref(), "slot", slot, header.hash().as_ref())
+		.map_err(|e| ConsensusError::ClientImport(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use futures::executor::block_on;
+	use node_template_runtime::opaque::{Block, Header};
+	use sp_api::ApiRef;
+	use sp_core::{crypto::Public, Encode, H256};
+	use sp_runtime::{generic::Digest, DigestItem};
+
+	#[derive(Clone)]
+	struct TestApi;
+
+	sp_api::mock_impl_runtime_apis! {
+		impl AuraApi<Block, AuraId> for TestApi {
+			fn slot_duration() -> u64 {
+				6000
+			}
+
+			fn authorities() -> Vec<AuraId> {
+				vec![AuraId::from_slice(&[1u8; 32])]
+			}
+		}
+	}
+
+	struct TestClient;
+
+	impl ProvideRuntimeApi<Block> for TestClient {
+		type Api = TestApi;
+
+		fn runtime_api<'a>(&'a self) -> ApiRef<'a, Self::Api> {
+			TestApi.into()
+		}
+	}
+
+	/// Proposes an empty block with the given extrinsics root.
+	struct TestProposer(H256);
+
+	impl Proposer<Block> for TestProposer {
+		type Error = ConsensusError;
+		type Transaction = ();
+		type Proposal = future::Ready<Result<Proposal<Block, ()>, ConsensusError>>;
+
+		fn propose(&mut self, _: InherentData, digest: DigestFor<Block>, _: Duration, _: RecordProof) -> Self::Proposal {
+			future::ready(Ok(Proposal {
+				block: Block::new(Header::new(1, self.0, H256::zero(), H256::zero(), digest), vec![]),
+				proof: None,
+				storage_changes: Default::default(),
+			}))
+		}
+	}
+
+	fn propose(guard: &SigningGuard, slot: u64, extrinsics_root: H256) -> Result<(), ConsensusError> {
+		let mut proposer = ProtectedProposer {
+			inner: TestProposer(extrinsics_root),
+			client: Arc::new(TestClient),
+			guard: guard.clone(),
+			_phantom: PhantomData,
+		};
+		let digest = Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+		block_on(proposer.propose(InherentData::new(), digest, Duration::from_secs(1), RecordProof::No))
+			.map(|_| ())
+	}
+
+	#[test]
+	fn second_block_for_a_slot_is_not_proposed() {
+		let dir = tempfile::tempdir().unwrap();
+		let guard = SigningGuard::open(dir.path()).unwrap();
+
+		assert!(propose(&guard, 3, H256::repeat_byte(1)).is_ok());
+		// The same block can be proposed again, e.g. after the first proposal timed out.
+		assert!(propose(&guard, 3, H256::repeat_byte(1)).is_ok());
+		assert!(propose(&guard, 3, H256::repeat_byte(2)).is_err());
+		assert!(propose(&guard, 2, H256::repeat_byte(3)).is_err());
+		assert!(propose(&guard, 4, H256::repeat_byte(4)).is_ok());
+	}
+}
@@ -0,0 +1,184 @@
+//! Snapshots of a chain's state, to inspect, dry-run or fork it without the node's database.
+//!
+//! A snapshot file starts with `MAGIC` and the little-endian `u16` format version, followed by
+//! the gzip-compressed SCALE encoding of a `Snapshot`.
+
+use std::{
+	fs::File,
+	io::{BufReader, BufWriter, Read, Write},
+	path::Path,
+};
+
+use codec::{Decode, Encode};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use node_template_runtime::{opaque::Block, BlockNumber};
+use sc_client_api::{Backend, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::storage::{well_known_keys, ChildInfo, Storage, StorageChild, StorageKey};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+/// Identifies snapshot files.
+const MAGIC: &[u8; 8] = b"nodesnap";
+
+/// The version of the snapshot format written by this node.
+const VERSION: u16 = 1;
+
+/// The complete storage of a chain at a block.
+#[derive(Debug, Encode, Decode)]
+pub struct Snapshot {
+	/// The block whose post-state this is.
+	pub block_hash: <Block as BlockT>::Hash,
+	/// The number of that block.
+	pub block_number: BlockNumber,
+	/// All key-value pairs of the top-level storage, without the roots of the child tries.
+	pub top: Vec<(Vec<u8>, Vec<u8>)>,
+	/// The key-value pairs of each default child trie, e.g. of a contract, by storage key.
+	pub children: Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>,
+}
+
+impl Snapshot {
+	/// Take a snapshot of the state at `at`, or at the best block if `None`.
+	pub fn from_client<B, C>(client: &C, at: Option<<Block as BlockT>::Hash>) -> Result<Self, String> where
+		B: Backend<Block>,
+		C: StorageProvider<Block, B> + HeaderBackend<Block>,
+	{
+		let block_hash = at.unwrap_or_else(|| client.info().best_hash);
+		let block_id = BlockId::Hash(block_hash);
+		let block_number = client.number(block_hash)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Unknown block {}", block_hash))?;
+		let read_error = |e: sp_blockchain::Error| format!("Failed to read the state at {}: {}", block_hash, e);
+
+		let mut top = Vec::new();
+		let mut children = Vec::new();
+		for (key, value) in client.storage_pairs(&block_id, &StorageKey(Vec::new())).map_err(read_error)? {
+			if !key.0.starts_with(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+				top.push((key.0, value.0));
+				continue;
+			}
+
+			// The child trie's root is recomputed from its content, so only the content is kept.
+			let storage_key = key.0[well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX.len()..].to_vec();
+			let child_info = ChildInfo::new_default(&storage_key);
+			let mut pairs = Vec::new();
+			for child_key in client.child_storage_keys(&block_id, &child_info, &StorageKey(Vec::new()))
+				.map_err(read_error)?
+			{
+				if let Some(value) = client.child_storage(&block_id, &child_info, &child_key)
+					.map_err(read_error)?
+				{
+					pairs.push((child_key.0, value.0));
+				}
+			}
+			children.push((storage_key, pairs));
+		}
+
+		Ok(Snapshot { block_hash, block_number, top, children })
+	}
+
+	/// Read a snapshot from a file.
+	pub fn load(path: &Path) -> Result<Self, String> {
+		let error = |e: String| format!("Invalid snapshot {}: {}", path.display(), e);
+		let mut file = BufReader::new(File::open(path).map_err(|e| error(e.to_string()))?);
+
+		let mut header = [0u8; 10];
+		file.read_exact(&mut header).map_err(|e| error(e.to_string()))?;
+		if &header[..8] != MAGIC {
+			return Err(error("not a snapshot file".into()));
+		}
+		let version = u16::from_le_bytes([header[8], header[9]]);
+		if version != VERSION {
+			return Err(error(format!("unsupported version {}, expected {}", version, VERSION)));
+		}
+
+		let mut bytes = Vec::new();
+		GzDecoder::new(file).read_to_end(&mut bytes).map_err(|e| error(e.to_string()))?;
+		Snapshot::decode(&mut &bytes[..]).map_err(|e| error(e.what().into()))
+	}
+
+	/// Write the snapshot to a file.
+	pub fn save(&self, path: &Path) -> Result<(), String> {
+		let error = |e: std::io::Error| format!("Failed to write snapshot {}: {}", path.display(), e);
+		let mut file = BufWriter::new(File::create(path).map_err(error)?);
+
+		file.write_all(MAGIC).map_err(error)?;
+		file.write_all(&VERSION.to_le_bytes()).map_err(error)?;
+		let mut encoder = GzEncoder::new(file, Compression::default());
+		encoder.write_all(&self.encode()).map_err(error)?;
+		encoder.finish().map_err(error)?.flush().map_err(error)
+	}
+
+	/// The snapshot as genesis storage, e.g. for `BasicExternalities` or a chain spec.
+	pub fn into_storage(self) -> Storage {
+		Storage {
+			top: self.top.into_iter().collect(),
+			children_default: self.children.into_iter()
+				.map(|(storage_key, pairs)| {
+					let child = StorageChild {
+						data: pairs.into_iter().collect(),
+						child_info: ChildInfo::new_default(&storage_key),
+					};
+					(storage_key, child)
+				})
+				.collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::{fs, path::PathBuf};
+
+	fn temp_path(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("node-template-{}-{}.snap", name, std::process::id()))
+	}
+
+	fn snapshot() -> Snapshot {
+		Snapshot {
+			block_hash: [7u8; 32].into(),
+			block_number: 42,
+			top: vec![(b"key".to_vec(), b"value".to_vec())],
+			children: vec![(b"child".to_vec(), vec![(b"child key".to_vec(), b"child value".to_vec())])],
+		}
+	}
+
+	#[test]
+	fn saved_snapshots_load_again() {
+		let path = temp_path("round-trip");
+		snapshot().save(&path).unwrap();
+		let loaded = Snapshot::load(&path);
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(loaded.unwrap().encode(), snapshot().encode());
+	}
+
+	#[test]
+	fn files_without_magic_are_rejected() {
+		let path = temp_path("magic");
+		snapshot().save(&path).unwrap();
+		let mut bytes = fs::read(&path).unwrap();
+		bytes[..MAGIC.len()].copy_from_slice(b"notasnap");
+		fs::write(&path, bytes).unwrap();
+		let loaded = Snapshot::load(&path);
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(loaded.unwrap_err(), format!("Invalid snapshot {}: not a snapshot file", path.display()));
+	}
+
+	#[test]
+	fn other_versions_are_rejected() {
+		let path = temp_path("version");
+		snapshot().save(&path).unwrap();
+		let mut bytes = fs::read(&path).unwrap();
+		bytes[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&(VERSION + 1).to_le_bytes());
+		fs::write(&path, bytes).unwrap();
+		let loaded = Snapshot::load(&path);
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(
+			loaded.unwrap_err(),
+			format!("Invalid snapshot {}: unsupported version {}, expected {}", path.display(), VERSION + 1, VERSION),
+		);
+	}
+}
@@ -0,0 +1,233 @@
+//! The `export-state` and `import-state` subcommands, which fork a chain's state into a new
+//! chain.
+
+use std::{fs, path::PathBuf};
+
+use codec::Encode;
+use node_template_runtime::{opaque::Block, BlockNumber, BuildStorage, Forcing, RuntimeApi};
+use sc_cli::{CliConfiguration, ImportParams, SharedParams};
+use sc_service::Configuration;
+use sp_blockchain::HeaderBackend;
+use sp_core::{hexdisplay::HexDisplay, storage::Storage, twox_128};
+use sp_finality_grandpa::GRANDPA_AUTHORITIES_KEY;
+use sp_runtime::traits::Block as BlockT;
+use structopt::StructOpt;
+
+use crate::{chain_spec, service::Executor, snapshot::Snapshot};
+
+/// The storage items, by pallet and item name, that decide who authors and finalizes blocks
+/// and who is root.
+const AUTHORITY_ITEMS: &[(&[u8], &[u8])] = &[
+	(b"Aura", b"Authorities"),
+	(b"Babe", b"Authorities"),
+	(b"GrandpaFinality", b"State"),
+	(b"GrandpaFinality", b"PendingChange"),
+	(b"GrandpaFinality", b"NextForced"),
+	(b"GrandpaFinality", b"Stalled"),
+	(b"Session", b"Validators"),
+	(b"Session", b"QueuedChanged"),
+	(b"Session", b"QueuedKeys"),
+	(b"Session", b"DisabledValidators"),
+	(b"Session", b"NextKeys"),
+	(b"Session", b"KeyOwner"),
+	(b"Sudo", b"Key"),
+];
+
+fn storage_prefix(pallet: &[u8], item: &[u8]) -> Vec<u8> {
+	[twox_128(pallet), twox_128(item)].concat()
+}
+
+/// Dump the complete state of the chain at a block into a snapshot file.
+#[derive(Debug, StructOpt)]
+pub struct ExportStateCmd {
+	/// The block to export the state of, by number or by `0x`-prefixed hash. Defaults to the
+	/// best block.
+	pub block: Option<String>,
+
+	/// The snapshot file to write.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl ExportStateCmd {
+	/// Run the command.
+	pub fn run(&self, config: Configuration) -> sc_cli::Result<()> {
+		let client = sc_service::new_full_client::<Block, RuntimeApi, Executor>(&config)?;
+
+		let at: Option<<Block as BlockT>::Hash> = match &self.block {
+			None => None,
+			Some(block) if block.starts_with("0x") => Some(block[2..].parse()
+				.map_err(|e| sc_cli::Error::Input(format!("Invalid block hash {}: {:?}", block, e)))?),
+			Some(block) => {
+				let number: BlockNumber = block.parse()
+					.map_err(|e| sc_cli::Error::Input(format!("Invalid block number {}: {}", block, e)))?;
+				Some(client.hash(number)?
+					.ok_or_else(|| sc_cli::Error::Input(format!("Unknown block {}", number)))?)
+			},
+		};
+
+		let snapshot = Snapshot::from_client(&client, at)?;
+		snapshot.save(&self.output)?;
+
+		println!(
+			"Exported the state of block #{} ({}) with {} keys and {} child tries.",
+			snapshot.block_number,
+			snapshot.block_hash,
+			snapshot.top.len(),
+			snapshot.children.len(),
+		);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ExportStateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
+
+/// Build a raw chain spec for a new chain whose genesis state is a snapshot.
+///
+/// Name, properties and everything else but the genesis are taken from the `--chain` spec.
+/// The new chain keeps the snapshot's runtime, but its authorities, session keys and sudo key
+/// are replaced by those of the development chain, so that `//Alice` authors and finalizes
+/// blocks and is root. No new era is started, which would elect the exported chain's
+/// validators again.
+#[derive(Debug, StructOpt)]
+pub struct ImportStateCmd {
+	/// The snapshot file to read.
+	#[structopt(parse(from_os_str))]
+	pub snapshot: PathBuf,
+
+	/// The chain spec file to write.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: PathBuf,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+impl ImportStateCmd {
+	/// Run the command.
+	pub fn run(&self, config: Configuration) -> sc_cli::Result<()> {
+		let snapshot = Snapshot::load(&self.snapshot)?;
+		let (block_number, block_hash) = (snapshot.block_number, snapshot.block_hash);
+		let mut storage = snapshot.into_storage();
+		use_dev_authorities(&mut storage, &chain_spec::development_config().build_storage()?);
+
+		let hex = |bytes: &[u8]| serde_json::Value::String(format!("0x{}", HexDisplay::from(&bytes)));
+		let top: serde_json::Map<_, _> = storage.top.iter()
+			.map(|(key, value)| (format!("0x{}", HexDisplay::from(key)), hex(value)))
+			.collect();
+		let children: serde_json::Map<_, _> = storage.children_default.iter()
+			.map(|(storage_key, child)| {
+				let data: serde_json::Map<_, _> = child.data.iter()
+					.map(|(key, value)| (format!("0x{}", HexDisplay::from(key)), hex(value)))
+					.collect();
+				(format!("0x{}", HexDisplay::from(storage_key)), data.into())
+			})
+			.collect();
+
+		let mut spec: serde_json::Value = serde_json::from_str(&config.chain_spec.as_json(true)?)
+			.map_err(|e| format!("Invalid chain spec: {}", e))?;
+		spec["id"] = format!("{}_fork", config.chain_spec.id()).into();
+		spec["bootNodes"] = serde_json::Value::Array(Vec::new());
+		spec["genesis"] = serde_json::json!({
+			"raw": {
+				"top": top,
+				"childrenDefault": children,
+			}
+		});
+
+		let json = serde_json::to_string_pretty(&spec)
+			.map_err(|e| format!("Failed to encode the chain spec: {}", e))?;
+		fs::write(&self.output, json)?;
+
+		println!(
+			"Wrote a chain spec forked from block #{} ({}) to {}.",
+			block_number,
+			block_hash,
+			self.output.display(),
+		);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for ImportStateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// Replace the authorities and the sudo key of `storage` by those of the `dev` genesis.
+fn use_dev_authorities(storage: &mut Storage, dev: &Storage) {
+	let prefixes = AUTHORITY_ITEMS.iter()
+		.map(|(pallet, item)| storage_prefix(pallet, item))
+		.chain(Some(GRANDPA_AUTHORITIES_KEY.to_vec()));
+	for prefix in prefixes {
+		let stale: Vec<_> = storage.top.keys().filter(|key| key.starts_with(&prefix)).cloned().collect();
+		for key in stale {
+			storage.top.remove(&key);
+		}
+		storage.top.extend(dev.top.iter()
+			.filter(|(key, _)| key.starts_with(&prefix))
+			.map(|(key, value)| (key.clone(), value.clone())));
+	}
+
+	storage.top.insert(storage_prefix(b"Staking", b"ForceEra"), Forcing::ForceNone.encode());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn storage(pairs: &[(Vec<u8>, &[u8])]) -> Storage {
+		Storage {
+			top: pairs.iter().map(|(key, value)| (key.clone(), value.to_vec())).collect(),
+			children_default: Default::default(),
+		}
+	}
+
+	#[test]
+	fn authorities_and_sudo_key_are_replaced() {
+		let next_keys = |account: &[u8]| [storage_prefix(b"Session", b"NextKeys"), account.to_vec()].concat();
+		let mut fork = storage(&[
+			(storage_prefix(b"Aura", b"Authorities"), b"validators"),
+			(GRANDPA_AUTHORITIES_KEY.to_vec(), b"validators"),
+			(next_keys(b"validator"), b"keys"),
+			(storage_prefix(b"Sudo", b"Key"), b"root"),
+			(storage_prefix(b"Balances", b"TotalIssuance"), b"issuance"),
+		]);
+		let dev = storage(&[
+			(storage_prefix(b"Aura", b"Authorities"), b"alice"),
+			(GRANDPA_AUTHORITIES_KEY.to_vec(), b"alice"),
+			(next_keys(b"alice"), b"alice keys"),
+			(storage_prefix(b"Sudo", b"Key"), b"alice"),
+			(storage_prefix(b"Balances", b"TotalIssuance"), b"dev issuance"),
+		]);
+
+		use_dev_authorities(&mut fork, &dev);
+
+		assert_eq!(fork.top, storage(&[
+			(storage_prefix(b"Aura", b"Authorities"), b"alice"),
+			(GRANDPA_AUTHORITIES_KEY.to_vec(), b"alice"),
+			(next_keys(b"alice"), b"alice keys"),
+			(storage_prefix(b"Sudo", b"Key"), b"alice"),
+			(storage_prefix(b"Balances", b"TotalIssuance"), b"issuance"),
+			(storage_prefix(b"Staking", b"ForceEra"), &Forcing::ForceNone.encode()),
+		]).top);
+	}
+}
@@ -0,0 +1,98 @@
+//! The `try-runtime` subcommand, which dry-runs a runtime upgrade against existing state.
+
+use std::{fs, path::PathBuf};
+
+use codec::Decode;
+use node_template_runtime::{opaque::Block, RuntimeApi, Weight};
+use sc_cli::{CliConfiguration, ImportParams, SharedParams};
+use sc_service::Configuration;
+use sp_core::storage::well_known_keys;
+use sp_runtime::traits::Block as BlockT;
+use sp_state_machine::BasicExternalities;
+use structopt::StructOpt;
+
+use crate::{service::Executor, snapshot::Snapshot, wasm};
+
+/// Run the `on_runtime_upgrade` hooks of a new runtime against the state of the chain and
+/// report the weight they consume and any failed post-upgrade check.
+///
+/// Nothing is written back, neither to the database nor to the snapshot.
+#[derive(Debug, StructOpt)]
+pub struct TryRuntimeCmd {
+	/// The WASM blob of the runtime to upgrade to.
+	#[structopt(long, parse(from_os_str))]
+	pub wasm: PathBuf,
+
+	/// Load the state from this snapshot file instead of the node's database.
+	#[structopt(long, parse(from_os_str))]
+	pub snapshot: Option<PathBuf>,
+
+	/// Hash of the block whose state is loaded from the database. Defaults to the best block.
+	#[structopt(long, conflicts_with = "snapshot")]
+	pub at: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl TryRuntimeCmd {
+	/// Run the command.
+	pub fn run(&self, config: Configuration) -> sc_cli::Result<()> {
+		let snapshot = match &self.snapshot {
+			Some(path) => Snapshot::load(path)?,
+			None => {
+				let client = sc_service::new_full_client::<Block, RuntimeApi, Executor>(&config)?;
+				Snapshot::from_client(&client, self.at()?)?
+			},
+		};
+		let block_hash = snapshot.block_hash;
+
+		let code = fs::read(&self.wasm)?;
+		let mut storage = snapshot.into_storage();
+		storage.top.insert(well_known_keys::CODE.to_vec(), code.clone());
+		let mut ext = BasicExternalities::new(storage);
+
+		let result = wasm::call(&config, &code, "TryRuntime_on_runtime_upgrade", &[], &mut ext)?;
+
+		let (weight, failures) = <(Weight, Vec<Vec<u8>>)>::decode(&mut &result[..])
+			.map_err(|e| format!("Invalid result of on_runtime_upgrade: {}", e.what()))?;
+
+		println!("Upgraded the state of block {}.", block_hash);
+		println!("Weight consumed: {} ({}% of a block)",
+			weight,
+			weight.saturating_mul(100) / node_template_runtime::MaximumBlockWeight::get(),
+		);
+
+		if failures.is_empty() {
+			println!("All post-upgrade checks passed.");
+			Ok(())
+		} else {
+			for failure in &failures {
+				eprintln!("Post-upgrade check failed: {}", String::from_utf8_lossy(failure));
+			}
+			Err(format!("{} post-upgrade check(s) failed", failures.len()).into())
+		}
+	}
+
+	fn at(&self) -> sc_cli::Result<Option<<Block as BlockT>::Hash>> {
+		self.at.as_ref()
+			.map(|hash| hash.trim_start_matches("0x").parse()
+				.map_err(|e| sc_cli::Error::Input(format!("Invalid block hash {}: {:?}", hash, e))))
+			.transpose()
+	}
+}
+
+impl CliConfiguration for TryRuntimeCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
@@ -0,0 +1,115 @@
+//! The `upgrade-check` subcommand, which vets a new runtime before it is enacted.
+
+use std::{fs, path::PathBuf};
+
+use codec::{Compact, Encode};
+use node_template_runtime::{opaque::Block, RuntimeApi};
+use sc_cli::{CliConfiguration, ImportParams, SharedParams};
+use sc_client_api::StorageProvider;
+use sc_service::Configuration;
+use sp_blockchain::HeaderBackend;
+use sp_core::{hexdisplay::HexDisplay, storage::{well_known_keys, StorageKey}};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use structopt::StructOpt;
+
+use crate::{metadata_diff, service::Executor, wasm};
+
+/// Check a new runtime against the chain's current one and build the `sudo(set_code)` call
+/// that enacts it.
+///
+/// Fails if the `spec_name` differs or the `spec_version` was not increased, as the node would
+/// otherwise keep executing the old native runtime or refuse the new one. Also fails if the
+/// metadata has breaking changes but the `transaction_version` was not increased.
+#[derive(Debug, StructOpt)]
+pub struct UpgradeCheckCmd {
+	/// The WASM blob of the runtime to upgrade to.
+	#[structopt(long, parse(from_os_str))]
+	pub wasm: PathBuf,
+
+	/// Hash of the block whose runtime is compared with. Defaults to the best block.
+	#[structopt(long)]
+	pub at: Option<String>,
+
+	/// Write the hex-encoded `sudo(set_code)` call to this file instead of stdout.
+	#[structopt(long, short, parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl UpgradeCheckCmd {
+	/// Run the command.
+	pub fn run(&self, config: Configuration) -> sc_cli::Result<()> {
+		let client = sc_service::new_full_client::<Block, RuntimeApi, Executor>(&config)?;
+		let at = self.at()?.unwrap_or_else(|| client.info().best_hash);
+		let old_code = client.storage(&BlockId::Hash(at), &StorageKey(well_known_keys::CODE.to_vec()))?
+			.ok_or_else(|| format!("No runtime code at block {}", at))?
+			.0;
+		let new_code = fs::read(&self.wasm)?;
+
+		let old_version = wasm::runtime_version(&config, &old_code)?;
+		let new_version = wasm::runtime_version(&config, &new_code)?;
+		println!("Current runtime: {}", old_version);
+		println!("New runtime:     {}", new_version);
+
+		if new_version.spec_name != old_version.spec_name {
+			return Err(format!(
+				"The spec_name changed from {} to {}",
+				old_version.spec_name,
+				new_version.spec_name,
+			).into());
+		}
+		if new_version.spec_version <= old_version.spec_version {
+			return Err(format!(
+				"The spec_version must be increased above {}, but is {}",
+				old_version.spec_version,
+				new_version.spec_version,
+			).into());
+		}
+
+		let old_metadata = wasm::metadata(&config, &old_code)?;
+		let new_metadata = wasm::metadata(&config, &new_code)?;
+		let diff = metadata_diff::diff(&old_metadata, &new_metadata)?;
+		print!("{}", diff);
+		metadata_diff::check_transaction_version(&diff, &old_version, &new_version)?;
+
+		// The call is dispatched by the current runtime, so its indices come from its metadata.
+		let mut call = metadata_diff::call_index(&old_metadata, "Sudo", "sudo")?.to_vec();
+		call.extend_from_slice(&metadata_diff::call_index(&old_metadata, "System", "set_code")?);
+		Compact(new_code.len() as u32).encode_to(&mut call);
+		call.extend_from_slice(&new_code);
+
+		let call = format!("0x{}", HexDisplay::from(&call));
+		match &self.output {
+			Some(path) => {
+				fs::write(path, call)?;
+				println!("Wrote the sudo(set_code) call to {}.", path.display());
+			},
+			None => println!("{}", call),
+		}
+		Ok(())
+	}
+
+	fn at(&self) -> sc_cli::Result<Option<<Block as BlockT>::Hash>> {
+		self.at.as_ref()
+			.map(|hash| hash.trim_start_matches("0x").parse()
+				.map_err(|e| sc_cli::Error::Input(format!("Invalid block hash {}: {:?}", hash, e))))
+			.transpose()
+	}
+}
+
+impl CliConfiguration for UpgradeCheckCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		Some(&self.import_params)
+	}
+}
@@ -0,0 +1,62 @@
+//! Calling into runtime WASM blobs that are not, or not yet, the chain's runtime.
+
+use codec::Decode;
+use frame_metadata::RuntimeMetadataPrefixed;
+use sc_executor::WasmExecutor;
+use sc_service::Configuration;
+use sp_core::traits::{CallInWasm, Externalities, MissingHostFunctions};
+use sp_state_machine::BasicExternalities;
+use sp_version::RuntimeVersion;
+use sp_wasm_interface::HostFunctions;
+
+/// Call the runtime function `method` of `code` with the given externalities.
+pub fn call(
+	config: &Configuration,
+	code: &[u8],
+	method: &str,
+	data: &[u8],
+	ext: &mut dyn Externalities,
+) -> Result<Vec<u8>, String> {
+	let executor = WasmExecutor::new(
+		config.wasm_method,
+		config.default_heap_pages,
+		sp_io::SubstrateHostFunctions::host_functions(),
+		1,
+	);
+	executor.call_in_wasm(code, None, method, data, ext, MissingHostFunctions::Disallow)
+		.map_err(|e| format!("Calling {} failed: {}", method, e))
+}
+
+/// The `RuntimeVersion` of `code`.
+pub fn runtime_version(config: &Configuration, code: &[u8]) -> Result<RuntimeVersion, String> {
+	let result = call(config, code, "Core_version", &[], &mut BasicExternalities::default())?;
+	RuntimeVersion::decode(&mut &result[..])
+		.map_err(|e| format!("Invalid runtime version: {}", e.what()))
+}
+
+/// The metadata of `code`.
+pub fn metadata(config: &Configuration, code: &[u8]) -> Result<RuntimeMetadataPrefixed, String> {
+	let result = call(config, code, "Metadata_metadata", &[], &mut BasicExternalities::default())?;
+	// The runtime API returns `OpaqueMetadata`, i.e. the encoded metadata as bytes.
+	let bytes = Vec::<u8>::decode(&mut &result[..])
+		.map_err(|e| format!("Invalid metadata: {}", e.what()))?;
+	RuntimeMetadataPrefixed::decode(&mut &bytes[..])
+		.map_err(|e| format!("Invalid metadata: {}", e.what()))
+}
+
+/// Ensure the embedded Wasm runtime was built for the same consensus engine as the native one.
+///
+/// The consensus engine is part of the `spec_name`, so this catches a Wasm binary that was built
+/// without the `babe-consensus` feature of the native runtime, or the other way around.
+pub fn check_wasm_binary(config: &Configuration) -> Result<(), String> {
+	let version = runtime_version(config, node_template_runtime::WASM_BINARY)?;
+	if version.spec_name != node_template_runtime::VERSION.spec_name {
+		return Err(format!(
+			"The Wasm runtime is {} but the native runtime is {}; build both with the same \
+			`babe-consensus` feature",
+			version.spec_name,
+			node_template_runtime::VERSION.spec_name,
+		));
+	}
+	Ok(())
+}
@@ -0,0 +1,154 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+/// A FRAME pallet that keeps a record of how reliably each Aura authority authors blocks.
+///
+/// Aura assigns slot `s` to authority `s % n`. Every block carries its slot in a pre-runtime
+/// digest, so each block credits its author with an authored slot, and every slot skipped
+/// since the previous block counts as missed for the authority it was assigned to. When an
+/// authority misses `MissedSlotsThreshold` slots in a row it is reported as down, and as
+/// recovered once it authors again.
+///
+/// Slots skipped while the authority set changed are attributed with the new set.
+
+use sp_std::prelude::*;
+use codec::{Decode, Encode};
+use frame_support::{decl_module, decl_storage, decl_event, Parameter, traits::Get, weights::Weight};
+use frame_system::{self as system};
+use sp_consensus_aura::AURA_ENGINE_ID;
+use sp_runtime::{traits::Member, RuntimeDebug};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// The pallet's configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The identifier of an authority.
+	type AuthorityId: Member + Parameter;
+
+	/// The current Aura authorities, in the order slots are assigned to them.
+	type Authorities: Get<Vec<Self::AuthorityId>>;
+
+	/// The number of slots in a row an authority must miss to be reported as down.
+	type MissedSlotsThreshold: Get<u32>;
+}
+
+/// The block authoring record of an authority.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct AuthorityStats {
+	/// The number of slots the authority authored a block in.
+	pub authored: u64,
+	/// The number of slots assigned to the authority without a block.
+	pub missed: u64,
+	/// The number of slots missed since the authority last authored a block.
+	pub missed_in_a_row: u32,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as AuthorityHealth {
+		/// The slot of the previous block.
+		LastSlot get(fn last_slot): Option<u64>;
+
+		/// The authoring record of each authority.
+		Stats get(fn stats): map hasher(blake2_128_concat) T::AuthorityId => AuthorityStats;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where AuthorityId = <T as Trait>::AuthorityId {
+		/// An authority missed `MissedSlotsThreshold` slots in a row. \[authority, missed slots
+		/// in a row\]
+		AuthorityDown(AuthorityId, u32),
+		/// An authority that was reported as down authored a block again.
+		AuthorityRecovered(AuthorityId),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		fn on_initialize() -> Weight {
+			let authorities = T::Authorities::get();
+			let slot = match Self::current_slot() {
+				Some(slot) if !authorities.is_empty() => slot,
+				_ => return T::DbWeight::get().reads(1),
+			};
+
+			let skipped = Self::last_slot()
+				.map(|last| slot.saturating_sub(last).saturating_sub(1))
+				.unwrap_or_default();
+			if skipped > 0 {
+				Self::note_missed(&authorities, slot - skipped, skipped);
+			}
+			Self::note_authored(&authorities[(slot % authorities.len() as u64) as usize]);
+			LastSlot::put(slot);
+
+			let accessed = authorities.len() as Weight + 2;
+			T::DbWeight::get().reads_writes(accessed, accessed)
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The slot of the current block, from its Aura pre-runtime digest.
+	fn current_slot() -> Option<u64> {
+		<system::Module<T>>::digest().logs().iter()
+			.filter_map(|item| item.as_pre_runtime())
+			.find(|(id, _)| *id == AURA_ENGINE_ID)
+			.and_then(|(_, mut data)| u64::decode(&mut data).ok())
+	}
+
+	/// Count the `count` slots from `first` on as missed by the authorities assigned to them.
+	fn note_missed(authorities: &[T::AuthorityId], first: u64, count: u64) {
+		let len = authorities.len() as u64;
+		let threshold = T::MissedSlotsThreshold::get();
+		for (index, authority) in authorities.iter().enumerate() {
+			// Every authority gets one slot per round, and the partial round left over goes to
+			// the authorities following the one of `first`.
+			let offset = (index as u64 + len - first % len) % len;
+			let missed = count / len + if offset < count % len { 1 } else { 0 };
+			if missed == 0 {
+				continue;
+			}
+
+			let crossed = <Stats<T>>::mutate(authority, |stats| {
+				let before = stats.missed_in_a_row;
+				stats.missed = stats.missed.saturating_add(missed);
+				stats.missed_in_a_row = before.saturating_add(missed.min(u32::max_value().into()) as u32);
+				before < threshold && stats.missed_in_a_row >= threshold
+			});
+			if crossed {
+				let missed_in_a_row = Self::stats(authority).missed_in_a_row;
+				Self::deposit_event(RawEvent::AuthorityDown(authority.clone(), missed_in_a_row));
+			}
+		}
+	}
+
+	fn note_authored(author: &T::AuthorityId) {
+		let was_down = <Stats<T>>::mutate(author, |stats| {
+			let was_down = stats.missed_in_a_row >= T::MissedSlotsThreshold::get();
+			stats.authored = stats.authored.saturating_add(1);
+			stats.missed_in_a_row = 0;
+			was_down
+		});
+		if was_down {
+			Self::deposit_event(RawEvent::AuthorityRecovered(author.clone()));
+		}
+	}
+
+	/// The authoring record of each current authority.
+	pub fn authority_stats() -> Vec<(T::AuthorityId, AuthorityStats)> {
+		T::Authorities::get().into_iter()
+			.map(|authority| {
+				let stats = Self::stats(&authority);
+				(authority, stats)
+			})
+			.collect()
+	}
+}
@@ -0,0 +1,91 @@
+// Creating mock runtime here
+
+use crate::{Module, Trait};
+use codec::Encode;
+use sp_core::H256;
+use frame_support::{impl_outer_event, impl_outer_origin, parameter_types, traits::Get, weights::Weight};
+use sp_consensus_aura::AURA_ENGINE_ID;
+use sp_runtime::{
+	traits::{BlakeTwo256, IdentityLookup}, testing::{Digest, DigestItem, Header}, Perbill,
+};
+use frame_system::{self as system, InitKind};
+
+impl_outer_origin! {
+	pub enum Origin for Test {}
+}
+
+mod authority_health {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		system<T>,
+		authority_health<T>,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::from_percent(75);
+	pub const MissedSlotsThreshold: u32 = 3;
+}
+impl system::Trait for Test {
+	type Origin = Origin;
+	type Call = ();
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+}
+
+/// Authorities 1, 2 and 3, assigned slots 0, 1 and 2 of every round.
+pub struct Authorities;
+impl Get<Vec<u64>> for Authorities {
+	fn get() -> Vec<u64> {
+		vec![1, 2, 3]
+	}
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type AuthorityId = u64;
+	type Authorities = Authorities;
+	type MissedSlotsThreshold = MissedSlotsThreshold;
+}
+pub type System = system::Module<Test>;
+pub type AuthorityHealth = Module<Test>;
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+}
+
+// Initializes block `number`, authored in `slot`, and runs the pallet's `on_initialize`.
+pub fn author_block(number: u64, slot: u64) {
+	use frame_support::traits::OnInitialize;
+
+	let digest = Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+	System::initialize(&number, &Default::default(), &Default::default(), &digest, InitKind::Full);
+	AuthorityHealth::on_initialize(number);
+}
@@ -0,0 +1,94 @@
+// Tests to be written here
+
+use crate::{mock::*, AuthorityStats, RawEvent};
+
+fn stats(authored: u64, missed: u64, missed_in_a_row: u32) -> AuthorityStats {
+	AuthorityStats { authored, missed, missed_in_a_row }
+}
+
+fn events() -> Vec<TestEvent> {
+	System::events().into_iter().map(|record| record.event).collect()
+}
+
+#[test]
+fn authors_are_credited_with_their_slots() {
+	new_test_ext().execute_with(|| {
+		author_block(1, 10);
+		author_block(2, 11);
+		author_block(3, 12);
+
+		// Slot 10 belongs to authority 2, as 10 % 3 = 1.
+		assert_eq!(AuthorityHealth::authority_stats(), vec![
+			(1, stats(1, 0, 0)),
+			(2, stats(1, 0, 0)),
+			(3, stats(1, 0, 0)),
+		]);
+		assert_eq!(AuthorityHealth::last_slot(), Some(12));
+	});
+}
+
+#[test]
+fn skipped_slots_are_missed_by_their_authorities() {
+	new_test_ext().execute_with(|| {
+		author_block(1, 10);
+		// Slots 11 to 15 are skipped: authorities 3 and 1 miss two, authority 2 misses one.
+		author_block(2, 16);
+
+		assert_eq!(AuthorityHealth::authority_stats(), vec![
+			(1, stats(0, 2, 2)),
+			(2, stats(2, 1, 0)),
+			(3, stats(0, 2, 2)),
+		]);
+	});
+}
+
+#[test]
+fn blocks_without_aura_slot_are_ignored() {
+	new_test_ext().execute_with(|| {
+		System::initialize(
+			&1,
+			&Default::default(),
+			&Default::default(),
+			&Default::default(),
+			frame_system::InitKind::Full,
+		);
+		<AuthorityHealth as frame_support::traits::OnInitialize<u64>>::on_initialize(1);
+
+		assert_eq!(AuthorityHealth::last_slot(), None);
+		assert!(AuthorityHealth::authority_stats().iter().all(|(_, s)| *s == AuthorityStats::default()));
+	});
+}
+
+#[test]
+fn crossing_the_threshold_reports_the_authority_as_down() {
+	new_test_ext().execute_with(|| {
+		// Authority 1 is assigned slots 3, 6 and 9 and misses all of them.
+		for (number, slot) in [1, 2, 4, 5, 7, 8].iter().enumerate() {
+			author_block(number as u64 + 1, *slot);
+			assert!(events().is_empty());
+		}
+
+		author_block(7, 10);
+		assert_eq!(events(), vec![TestEvent::authority_health(RawEvent::AuthorityDown(1, 3))]);
+		assert_eq!(AuthorityHealth::stats(1), stats(0, 3, 3));
+
+		// Missing more slots does not report it again.
+		author_block(8, 13);
+		assert!(events().is_empty());
+		assert_eq!(AuthorityHealth::stats(1), stats(0, 4, 4));
+	});
+}
+
+#[test]
+fn authoring_again_reports_the_authority_as_recovered() {
+	new_test_ext().execute_with(|| {
+		for (number, slot) in [1, 2, 4, 5, 7, 8, 10].iter().enumerate() {
+			author_block(number as u64 + 1, *slot);
+		}
+		assert_eq!(AuthorityHealth::stats(1), stats(0, 3, 3));
+
+		author_block(8, 12);
+		assert_eq!(events(), vec![TestEvent::authority_health(RawEvent::AuthorityRecovered(1))]);
+		assert_eq!(AuthorityHealth::stats(1), stats(1, 3, 0));
+	});
+}
@@ -9,8 +9,21 @@
 /// For more guidance on Substrate FRAME, see the example pallet
 /// https://github.com/paritytech/substrate/blob/master/frame/example/src/lib.rs
 
-use frame_support::{decl_module, decl_storage, decl_event, decl_error, dispatch};
-use frame_system::{self as system, ensure_signed};
+use sp_std::prelude::*;
+use codec::{Decode, Encode};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, dispatch, ensure, Parameter,
+	storage::StorageValue,
+	IsSubType, traits::{Get, schedule::{Named as ScheduleNamed, LOWEST_PRORITY}},
+	weights::{GetDispatchInfo, PostDispatchInfo, Weight},
+};
+use frame_system::{self as system, ensure_signed, ensure_root};
+use sp_runtime::{RuntimeDebug, traits::{BadOrigin, Dispatchable, Zero}};
+
+mod payment;
+pub use payment::{ChargeNotaryCredits, FreeFirstCall};
+
+pub mod migrations;
 
 #[cfg(test)]
 mod mock;
@@ -24,6 +37,84 @@ pub trait Trait: system::Trait {
 
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// Length in blocks of the window in which each account may make one feeless
+	/// `do_something` call. Zero disables free calls.
+	type FreeCallWindow: Get<Self::BlockNumber>;
+
+	/// The maximum number of feeless calls all accounts together may make per free call window.
+	type MaxFreeCallsPerWindow: Get<u32>;
+
+	/// The overarching call type, used for calls scheduled through this pallet.
+	type Call: Parameter + Dispatchable<Origin=Self::Origin, PostInfo=PostDispatchInfo>
+		+ GetDispatchInfo + From<Call<Self>> + IsSubType<Module<Self>, Self>;
+
+	/// The scheduler that dispatches scheduled calls.
+	type Scheduler: ScheduleNamed<Self::BlockNumber, <Self as Trait>::Call>;
+
+	/// The maximum number of calls an account may have scheduled at once.
+	type MaxScheduledPerAccount: Get<u32>;
+
+	/// Resolves accounts to verified real-world identities.
+	type Identity: IdentityProvider<Self::AccountId>;
+
+	/// Identifies the asset classes a stored value can be bound to.
+	type AssetId: Parameter + Copy;
+
+	/// The asset classes a stored value can be bound to, see `bind_asset`.
+	type Assets: AssetProvider<Self::AccountId, Self::AssetId>;
+}
+
+/// Access to on-chain identities, e.g. those of `pallet-identity`.
+pub trait IdentityProvider<AccountId> {
+	/// The display name of `who`, if a registrar has judged its identity to be correct.
+	fn verified_display_name(who: &AccountId) -> Option<Vec<u8>>;
+
+	/// Make `who` a registrar. Used to set up the registrars at genesis.
+	fn add_registrar(who: AccountId) -> Result<(), &'static str>;
+}
+
+impl<AccountId> IdentityProvider<AccountId> for () {
+	fn verified_display_name(_: &AccountId) -> Option<Vec<u8>> { None }
+	fn add_registrar(_: AccountId) -> Result<(), &'static str> { Err("No identities to add registrars to") }
+}
+
+/// Access to fungible asset classes, e.g. those of `pallet-assets`.
+pub trait AssetProvider<AccountId, AssetId> {
+	/// Whether `who` holds every unit of the asset class `id`, which must have been issued.
+	fn holds_all_of(who: &AccountId, id: AssetId) -> bool;
+}
+
+impl<AccountId, AssetId> AssetProvider<AccountId, AssetId> for () {
+	fn holds_all_of(_: &AccountId, _: AssetId) -> bool { false }
+}
+
+/// Prefix of the scheduler task names used by this pallet.
+const SCHEDULE_ID_PREFIX: &[u8] = b"template/schedule";
+
+/// A value stored with `do_something`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct StoredValue<BlockNumber> {
+	/// The value itself.
+	pub value: u32,
+	/// The block in which the value was last changed.
+	pub updated_at: BlockNumber,
+}
+
+/// The storage layouts of this pallet, see `migrations`.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, RuntimeDebug)]
+pub enum Releases {
+	/// `Something` is a plain `u32`.
+	V1_0_0,
+	/// `Something` is a `StoredValue`.
+	V2_0_0,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		// Chains that predate the version being stored use the first layout.
+		Releases::V1_0_0
+	}
 }
 
 // This pallet's storage items.
@@ -33,19 +124,74 @@ decl_storage! {
 	// ---------------------------------vvvvvvvvvvvvvv
 	trait Store for Module<T: Trait> as TemplateModule {
 		// Just a dummy storage item.
-		// Here we are declaring a StorageValue, `Something` as a Option<u32>
-		// `get(fn something)` is the default getter which returns either the stored `u32` or `None` if nothing stored
-		Something get(fn something): Option<u32>;
+		// Here we are declaring a StorageValue, `Something` as a Option<StoredValue>
+		// `get(fn stored_value)` is the default getter which returns either the stored value or `None` if nothing stored
+		Something get(fn stored_value): Option<StoredValue<T::BlockNumber>>;
+
+		/// The storage layout of this pallet. New chains start with the latest one.
+		StorageVersion build(|_: &GenesisConfig<T>| Releases::V2_0_0): Releases;
+
+		/// Prepaid notary credits of an account. Each credit pays for one call into this
+		/// pallet instead of the native transaction fee, see `ChargeNotaryCredits`.
+		NotaryCredits get(fn notary_credits): map hasher(blake2_128_concat) T::AccountId => u32;
+
+		/// The last window, counted in `FreeCallWindow`s since genesis, in which an account
+		/// used its free call, see `FreeFirstCall`. Entries of earlier windows are stale.
+		LastFreeCall get(fn last_free_call): map hasher(blake2_128_concat) T::AccountId => Option<T::BlockNumber>;
+
+		/// The window of the last free call and the number of free calls made in it.
+		FreeCallsInWindow get(fn free_calls_in_window): (T::BlockNumber, u32);
+
+		/// The calls an account has scheduled, as `(index, when, call)`.
+		ScheduledCalls get(fn scheduled_calls):
+			map hasher(blake2_128_concat) T::AccountId => Vec<(u32, T::BlockNumber, <T as Trait>::Call)>;
+
+		/// The index the next call scheduled by an account gets.
+		NextScheduleIndex get(fn next_schedule_index): map hasher(blake2_128_concat) T::AccountId => u32;
+
+		/// The account that stored the current value. Only it may bind the value to an asset
+		/// class, and while the value is bound, only it may replace the value.
+		Owner get(fn owner): Option<T::AccountId>;
+
+		/// The asset class whose holders own the rights to the stored value, see `bind_asset`.
+		/// Cleared when a new value is stored.
+		BoundAsset get(fn bound_asset): Option<T::AssetId>;
+	}
+	add_extra_genesis {
+		/// Accounts that are made identity registrars at genesis. They charge no fee and judge
+		/// all identity fields.
+		config(identity_registrars): Vec<T::AccountId>;
+		build(|config: &GenesisConfig<T>| {
+			for registrar in &config.identity_registrars {
+				T::Identity::add_registrar(registrar.clone()).expect("genesis registrars can be added");
+			}
+		});
 	}
 }
 
 // The pallet's events
 decl_event!(
-	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId {
+	pub enum Event<T> where
+		AccountId = <T as system::Trait>::AccountId,
+		BlockNumber = <T as system::Trait>::BlockNumber,
+		AssetId = <T as Trait>::AssetId,
+	{
 		/// Just a dummy event.
 		/// Event `Something` is declared with a parameter of the type `u32` and `AccountId`
 		/// To emit this event, we call the deposit function, from our runtime functions
 		SomethingStored(u32, AccountId),
+		/// The account that stored a value has a verified identity. [who, display name]
+		OwnerIdentified(AccountId, Vec<u8>),
+		/// The notary credits of an account were set. [who, credits]
+		NotaryCreditsSet(AccountId, u32),
+		/// A call was scheduled on behalf of an account. [owner, index, when]
+		CallScheduled(AccountId, u32, BlockNumber),
+		/// A scheduled call was cancelled. [owner, index]
+		ScheduledCallCancelled(AccountId, u32),
+		/// A scheduled call was dispatched on behalf of its owner. [owner, index, result]
+		ScheduledCallDispatched(AccountId, u32, dispatch::DispatchResult),
+		/// The stored value was bound to an asset class. [who, asset]
+		AssetBound(AccountId, AssetId),
 	}
 );
 
@@ -56,6 +202,24 @@ decl_error! {
 		NoneValue,
 		/// Value reached maximum and cannot be incremented further
 		StorageOverflow,
+		/// Only calls of this pallet can be scheduled.
+		NotTemplateCall,
+		/// The call would be dispatched in the past.
+		InPast,
+		/// The account has scheduled too many calls.
+		TooManyScheduled,
+		/// The scheduled call does not exist.
+		NotScheduled,
+		/// The scheduler refused the call.
+		ScheduleFailed,
+		/// The stored value is already bound to an asset class.
+		AlreadyBound,
+		/// The sender does not hold every unit of the asset class.
+		NotWholeSupply,
+		/// The sender did not store the value.
+		NotOwner,
+		/// The stored value is bound to an asset class, and only its owner may replace it.
+		ValueBound,
 	}
 }
 
@@ -68,10 +232,20 @@ decl_module! {
 		// it is needed only if you are using errors in your pallet
 		type Error = Error<T>;
 
+		/// Length in blocks of the free call window.
+		const FreeCallWindow: T::BlockNumber = T::FreeCallWindow::get();
+
+		/// The maximum number of free calls per window.
+		const MaxFreeCallsPerWindow: u32 = T::MaxFreeCallsPerWindow::get();
+
 		// Initializing events
 		// this is needed only if you are using events in your pallet
 		fn deposit_event() = default;
 
+		fn on_runtime_upgrade() -> Weight {
+			migrations::migrate::<T>()
+		}
+
 		/// Just a dummy entry point.
 		/// function that can be called by the external world as an extrinsics call
 		/// takes a parameter of the type `AccountId`, stores it, and emits an event
@@ -79,13 +253,23 @@ decl_module! {
 		pub fn do_something(origin, something: u32) -> dispatch::DispatchResult {
 			// Check it was signed and get the signer. See also: ensure_root and ensure_none
 			let who = ensure_signed(origin)?;
+			Self::ensure_may_replace(&who)?;
 
 			// Code to execute when something calls this.
 			// For example: the following line stores the passed in u32 in the storage
-			Something::put(something);
+			<Something<T>>::put(StoredValue {
+				value: something,
+				updated_at: <system::Module<T>>::block_number(),
+			});
+			<Owner<T>>::put(&who);
+			// The holders of a bound asset own the rights to the old value, not the new one.
+			<BoundAsset<T>>::kill();
 
 			// Here we are raising the Something event
-			Self::deposit_event(RawEvent::SomethingStored(something, who));
+			Self::deposit_event(RawEvent::SomethingStored(something, who.clone()));
+			if let Some(name) = T::Identity::verified_display_name(&who) {
+				Self::deposit_event(RawEvent::OwnerIdentified(who, name));
+			}
 			Ok(())
 		}
 
@@ -94,16 +278,223 @@ decl_module! {
 		#[weight = 10_000]
 		pub fn cause_error(origin) -> dispatch::DispatchResult {
 			// Check it was signed and get the signer. See also: ensure_root and ensure_none
-			let _who = ensure_signed(origin)?;
+			let who = ensure_signed(origin)?;
+			Self::ensure_may_replace(&who)?;
 
-			match Something::get() {
+			match <Something<T>>::get() {
 				None => Err(Error::<T>::NoneValue)?,
 				Some(old) => {
-					let new = old.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
-					Something::put(new);
+					let new = old.value.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
+					<Something<T>>::put(StoredValue {
+						value: new,
+						updated_at: <system::Module<T>>::block_number(),
+					});
 					Ok(())
 				},
 			}
 		}
+
+		/// Set the number of notary credits of an account.
+		/// Can only be called by root, e.g. when a customer buys a service plan.
+		#[weight = 10_000]
+		pub fn set_notary_credits(origin, who: T::AccountId, credits: u32) -> dispatch::DispatchResult {
+			ensure_root(origin)?;
+
+			if credits == 0 {
+				<NotaryCredits<T>>::remove(&who);
+			} else {
+				<NotaryCredits<T>>::insert(&who, credits);
+			}
+
+			Self::deposit_event(RawEvent::NotaryCreditsSet(who, credits));
+			Ok(())
+		}
+
+		/// Schedule a call of this pallet to be dispatched on behalf of `owner` at block `when`.
+		///
+		/// Can be called by `owner` itself or by root. The call is listed in `ScheduledCalls`
+		/// until it is dispatched or cancelled.
+		#[weight = 50_000]
+		pub fn schedule_call(
+			origin,
+			owner: T::AccountId,
+			when: T::BlockNumber,
+			call: Box<<T as Trait>::Call>,
+		) -> dispatch::DispatchResult {
+			Self::ensure_owner_or_root(origin, &owner)?;
+			ensure!(call.is_sub_type().is_some(), Error::<T>::NotTemplateCall);
+			ensure!(when > <system::Module<T>>::block_number(), Error::<T>::InPast);
+
+			let mut scheduled = Self::scheduled_calls(&owner);
+			ensure!(
+				(scheduled.len() as u32) < T::MaxScheduledPerAccount::get(),
+				Error::<T>::TooManyScheduled
+			);
+
+			let index = Self::next_schedule_index(&owner);
+			// The scheduler dispatches as root, so wrap the call to dispatch it as `owner`.
+			let dispatch = Call::<T>::dispatch_scheduled(owner.clone(), index, call.clone());
+			T::Scheduler::schedule_named(
+				Self::schedule_id(&owner, index),
+				when,
+				None,
+				LOWEST_PRORITY,
+				dispatch.into(),
+			).map_err(|_| Error::<T>::ScheduleFailed)?;
+
+			scheduled.push((index, when, *call));
+			<ScheduledCalls<T>>::insert(&owner, scheduled);
+			<NextScheduleIndex<T>>::insert(&owner, index.wrapping_add(1));
+
+			Self::deposit_event(RawEvent::CallScheduled(owner, index, when));
+			Ok(())
+		}
+
+		/// Cancel a call scheduled on behalf of `owner`.
+		///
+		/// Can be called by `owner` itself or by root.
+		#[weight = 50_000]
+		pub fn cancel_scheduled_call(origin, owner: T::AccountId, index: u32) -> dispatch::DispatchResult {
+			Self::ensure_owner_or_root(origin, &owner)?;
+
+			// Dispatchables are not transactional, so only drop the record once the task is gone.
+			T::Scheduler::cancel_named(Self::schedule_id(&owner, index))
+				.map_err(|_| Error::<T>::NotScheduled)?;
+			Self::take_scheduled(&owner, index);
+
+			Self::deposit_event(RawEvent::ScheduledCallCancelled(owner, index));
+			Ok(())
+		}
+
+		/// Dispatch a scheduled call on behalf of its owner.
+		///
+		/// Called by the scheduler, which dispatches with the root origin.
+		#[weight = (call.get_dispatch_info().weight + 10_000, call.get_dispatch_info().class)]
+		pub fn dispatch_scheduled(
+			origin,
+			owner: T::AccountId,
+			index: u32,
+			call: Box<<T as Trait>::Call>,
+		) -> dispatch::DispatchResult {
+			ensure_root(origin)?;
+			Self::take_scheduled(&owner, index);
+
+			let result = call.dispatch(system::RawOrigin::Signed(owner.clone()).into());
+			Self::deposit_event(RawEvent::ScheduledCallDispatched(
+				owner,
+				index,
+				result.map(|_| ()).map_err(|e| e.error),
+			));
+			Ok(())
+		}
+
+		/// Bind the stored value to the asset class `asset`, so that the holders of the asset
+		/// collectively own the rights to the value, e.g. to split the revenue of a document.
+		///
+		/// The sender must have stored the value and hold every unit of `asset`, like its issuer
+		/// does right after issuing it. The binding lasts until the sender stores a new value.
+		#[weight = 10_000]
+		pub fn bind_asset(origin, asset: T::AssetId) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(<Something<T>>::exists(), Error::<T>::NoneValue);
+			ensure!(Self::owner().as_ref() == Some(&who), Error::<T>::NotOwner);
+			ensure!(!<BoundAsset<T>>::exists(), Error::<T>::AlreadyBound);
+			ensure!(T::Assets::holds_all_of(&who, asset), Error::<T>::NotWholeSupply);
+
+			<BoundAsset<T>>::put(asset);
+
+			Self::deposit_event(RawEvent::AssetBound(who, asset));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The value stored with `do_something`, if any.
+	pub fn something() -> Option<u32> {
+		Self::stored_value().map(|stored| stored.value)
+	}
+
+	/// The storage key of the value stored with `do_something`. Contracts read the encoded
+	/// `StoredValue` at this key with `ext_get_runtime_storage`.
+	pub fn stored_value_key() -> Vec<u8> {
+		<Something<T>>::hashed_key().to_vec()
+	}
+
+	/// The verified display name of `owner`, if it has one.
+	///
+	/// Lets clients show who stored a value or scheduled a call, rather than a bare account.
+	pub fn owner_display_name(owner: &T::AccountId) -> Option<Vec<u8>> {
+		T::Identity::verified_display_name(owner)
+	}
+
+	/// While the stored value is bound to an asset class, only its owner may replace it.
+	fn ensure_may_replace(who: &T::AccountId) -> dispatch::DispatchResult {
+		if <BoundAsset<T>>::exists() && Self::owner().as_ref() != Some(who) {
+			Err(Error::<T>::ValueBound)?
+		}
+		Ok(())
+	}
+
+	fn ensure_owner_or_root(origin: T::Origin, owner: &T::AccountId) -> dispatch::DispatchResult {
+		let origin: Result<system::RawOrigin<T::AccountId>, T::Origin> = origin.into();
+		match origin {
+			Ok(system::RawOrigin::Root) => Ok(()),
+			Ok(system::RawOrigin::Signed(ref who)) if who == owner => Ok(()),
+			_ => Err(BadOrigin.into()),
+		}
+	}
+
+	/// The scheduler task name of a scheduled call.
+	fn schedule_id(owner: &T::AccountId, index: u32) -> Vec<u8> {
+		(SCHEDULE_ID_PREFIX, owner, index).encode()
+	}
+
+	/// Remove a call from the list of scheduled calls of `owner`. Returns whether it was listed.
+	fn take_scheduled(owner: &T::AccountId, index: u32) -> bool {
+		let mut scheduled = Self::scheduled_calls(owner);
+		let len = scheduled.len();
+		scheduled.retain(|(i, _, _)| *i != index);
+		let found = scheduled.len() != len;
+
+		if scheduled.is_empty() {
+			<ScheduledCalls<T>>::remove(owner);
+		} else if found {
+			<ScheduledCalls<T>>::insert(owner, scheduled);
+		}
+		found
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The free call window the current block belongs to, or `None` if free calls are disabled.
+	pub fn current_free_call_window() -> Option<T::BlockNumber> {
+		let window = T::FreeCallWindow::get();
+		if window.is_zero() {
+			return None;
+		}
+		Some(<system::Module<T>>::block_number() / window)
+	}
+
+	/// The number of free calls made in `window` so far.
+	pub fn free_calls_made(window: T::BlockNumber) -> u32 {
+		match Self::free_calls_in_window() {
+			(last, made) if last == window => made,
+			_ => 0,
+		}
+	}
+
+	/// The current free call window if `who` has not used its free call in it yet and the
+	/// free calls of the window are not used up.
+	pub fn free_call_available(who: &T::AccountId) -> Option<T::BlockNumber> {
+		Self::current_free_call_window()
+			.filter(|window| Self::last_free_call(who) != Some(*window))
+			.filter(|window| Self::free_calls_made(*window) < T::MaxFreeCallsPerWindow::get())
+	}
+
+	/// Record that `who` used the free call of `window`.
+	fn use_free_call(who: &T::AccountId, window: T::BlockNumber) {
+		<LastFreeCall<T>>::insert(who, window);
+		<FreeCallsInWindow<T>>::put((window, Self::free_calls_made(window).saturating_add(1)));
 	}
 }
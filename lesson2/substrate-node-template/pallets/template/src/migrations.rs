@@ -0,0 +1,52 @@
+//! Storage migrations of the template pallet.
+//!
+//! `StorageVersion` records the layout the storage is in. `migrate` runs on every runtime
+//! upgrade and brings the storage up to the latest layout, one version at a time, so a chain
+//! can skip runtime versions. To change a layout, add a `Releases` variant, a module here that
+//! converts from the previous one, and a step to `migrate`.
+
+use frame_support::{ensure, storage::StorageValue, traits::Get, weights::Weight};
+use frame_system as system;
+use crate::{Releases, Something, StorageVersion, Trait};
+
+/// Migrate the storage to the latest layout. Returns the weight consumed.
+pub fn migrate<T: Trait>() -> Weight {
+	let mut weight = T::DbWeight::get().reads(1);
+
+	if StorageVersion::get() == Releases::V1_0_0 {
+		weight = weight.saturating_add(v2::migrate::<T>());
+		StorageVersion::put(Releases::V2_0_0);
+		weight = weight.saturating_add(T::DbWeight::get().writes(1));
+	}
+
+	weight
+}
+
+/// Check that the storage is in the latest layout, e.g. after dry-running a runtime upgrade.
+pub fn post_upgrade<T: Trait>() -> Result<(), &'static str> {
+	ensure!(
+		StorageVersion::get() == Releases::V2_0_0,
+		"TemplateModule: StorageVersion is not V2_0_0"
+	);
+	ensure!(
+		!<Something<T>>::exists() || <Something<T>>::get().is_some(),
+		"TemplateModule: Something does not decode as StoredValue"
+	);
+	Ok(())
+}
+
+/// `V1_0_0` to `V2_0_0`: `Something` becomes a `StoredValue`.
+pub mod v2 {
+	use super::*;
+	use crate::StoredValue;
+
+	/// Wrap the plain `u32` in a `StoredValue`. As the block of the last change is not known,
+	/// it is set to the block of the upgrade.
+	pub fn migrate<T: Trait>() -> Weight {
+		let now = <system::Module<T>>::block_number();
+		let _ = <Something<T>>::translate(|old: Option<u32>| {
+			old.map(|value| StoredValue { value, updated_at: now })
+		});
+		T::DbWeight::get().reads_writes(2, 1)
+	}
+}
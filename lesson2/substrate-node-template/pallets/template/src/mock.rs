@@ -1,8 +1,12 @@
 // Creating mock runtime here
 
-use crate::{Module, Trait};
+use std::cell::RefCell;
+use crate::{AssetProvider, IdentityProvider, Module, Trait};
+use crate as template;
 use sp_core::H256;
-use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+use frame_support::{
+	impl_outer_dispatch, impl_outer_event, impl_outer_origin, parameter_types, weights::{IdentityFee, Weight},
+};
 use sp_runtime::{
 	traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
 };
@@ -12,6 +16,23 @@ impl_outer_origin! {
 	pub enum Origin for Test {}
 }
 
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		system<T>,
+		pallet_balances<T>,
+		pallet_scheduler<T>,
+		template<T>,
+	}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Test where origin: Origin {
+		system::System,
+		pallet_balances::Balances,
+		template::TemplateModule,
+	}
+}
+
 // For testing the pallet, we construct most of a mock runtime. This means
 // first constructing a configuration type (`Test`) which `impl`s each of the
 // configuration traits of pallets we want to use.
@@ -25,7 +46,7 @@ parameter_types! {
 }
 impl system::Trait for Test {
 	type Origin = Origin;
-	type Call = ();
+	type Call = Call;
 	type Index = u64;
 	type BlockNumber = u64;
 	type Hash = H256;
@@ -33,7 +54,7 @@ impl system::Trait for Test {
 	type AccountId = u64;
 	type Lookup = IdentityLookup<Self::AccountId>;
 	type Header = Header;
-	type Event = ();
+	type Event = TestEvent;
 	type BlockHashCount = BlockHashCount;
 	type MaximumBlockWeight = MaximumBlockWeight;
 	type DbWeight = ();
@@ -44,17 +65,95 @@ impl system::Trait for Test {
 	type AvailableBlockRatio = AvailableBlockRatio;
 	type Version = ();
 	type ModuleToIndex = ();
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 }
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+}
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+}
+parameter_types! {
+	pub const TransactionByteFee: u64 = 1;
+}
+impl pallet_transaction_payment::Trait for Test {
+	type Currency = Balances;
+	type OnTransactionPayment = ();
+	type TransactionByteFee = TransactionByteFee;
+	type WeightToFee = IdentityFee<u64>;
+	type FeeMultiplierUpdate = ();
+}
+parameter_types! {
+	pub const MaximumSchedulerWeight: Weight = 1024;
+}
+impl pallet_scheduler::Trait for Test {
+	type Event = TestEvent;
+	type Origin = Origin;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+}
+parameter_types! {
+	pub const FreeCallWindow: u64 = 10;
+	pub const MaxFreeCallsPerWindow: u32 = 2;
+	pub const MaxScheduledPerAccount: u32 = 2;
+}
+thread_local! {
+	static REGISTRARS: RefCell<Vec<u64>> = RefCell::new(vec![]);
+}
+/// Account 1 has the verified display name "Alice", everyone else has no identity.
+pub struct TestIdentity;
+impl IdentityProvider<u64> for TestIdentity {
+	fn verified_display_name(who: &u64) -> Option<Vec<u8>> {
+		if *who == 1 { Some(b"Alice".to_vec()) } else { None }
+	}
+	fn add_registrar(who: u64) -> Result<(), &'static str> {
+		REGISTRARS.with(|r| r.borrow_mut().push(who));
+		Ok(())
+	}
+}
+pub fn registrars() -> Vec<u64> {
+	REGISTRARS.with(|r| r.borrow().clone())
+}
+/// Account 1 holds all of asset class 0, asset class 1 is shared by several accounts.
+pub struct TestAssets;
+impl AssetProvider<u64, u32> for TestAssets {
+	fn holds_all_of(who: &u64, id: u32) -> bool {
+		*who == 1 && id == 0
+	}
+}
 impl Trait for Test {
-	type Event = ();
+	type Event = TestEvent;
+	type FreeCallWindow = FreeCallWindow;
+	type MaxFreeCallsPerWindow = MaxFreeCallsPerWindow;
+	type Call = Call;
+	type Scheduler = Scheduler;
+	type MaxScheduledPerAccount = MaxScheduledPerAccount;
+	type Identity = TestIdentity;
+	type AssetId = u32;
+	type Assets = TestAssets;
 }
+pub type System = system::Module<Test>;
+pub type Balances = pallet_balances::Module<Test>;
+pub type Scheduler = pallet_scheduler::Module<Test>;
 pub type TemplateModule = Module<Test>;
 
 // This function basically just builds a genesis storage key/value store according to
 // our desired mockup.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	// Account 1 holds native tokens, account 2 holds none and account 4 just the existential
+	// deposit, which does not cover any fee.
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(1, 100), (4, 1)],
+	}.assimilate_storage(&mut t).unwrap();
+	template::GenesisConfig::<Test> {
+		identity_registrars: vec![3],
+	}.assimilate_storage(&mut t).unwrap();
+	t.into()
 }
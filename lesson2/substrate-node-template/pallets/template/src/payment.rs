@@ -0,0 +1,251 @@
+//! Paying for template-pallet calls with free calls and notary credits.
+
+use sp_std::prelude::*;
+use codec::{Decode, Encode};
+use frame_support::{
+	storage::StorageMap,
+	traits::{Currency, Get}, IsSubType,
+	weights::{DispatchInfo, PostDispatchInfo},
+};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use sp_runtime::{
+	traits::{
+		DispatchInfoOf, Dispatchable, One, PostDispatchInfoOf, Saturating, SignedExtension,
+		UniqueSaturatedInto,
+	},
+	transaction_validity::{TransactionValidity, TransactionValidityError, ValidTransaction},
+	DispatchResult,
+};
+use crate::{Call, Module, NotaryCredits, Trait};
+
+type BalanceOf<T> = <<T as pallet_transaction_payment::Trait>::Currency as Currency<
+	<T as frame_system::Trait>::AccountId,
+>>::Balance;
+
+/// Replacement for `ChargeTransactionPayment` in the runtime's `SignedExtra`.
+///
+/// A call into this pallet signed by an account holding notary credits consumes one credit
+/// and pays no fee, so the account does not need any native balance. Every other transaction,
+/// and template calls from accounts without credits, pay the normal fee (plus tip) through the
+/// wrapped `ChargeTransactionPayment`.
+///
+/// A credit is only consumed when the transaction is dispatched, so a valid credit-paid
+/// transaction provides a tag unique to the account and its current credits. The pool thereby
+/// holds at most one credit-paid transaction per account, and cannot be filled with more
+/// transactions than the account's credits can pay for.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ChargeNotaryCredits<T: Trait + pallet_transaction_payment::Trait + Send + Sync>(
+	ChargeTransactionPayment<T>,
+);
+
+impl<T: Trait + pallet_transaction_payment::Trait + Send + Sync> ChargeNotaryCredits<T> where
+	BalanceOf<T>: Send + Sync,
+	<T as frame_system::Trait>::Call: Dispatchable<Info=DispatchInfo, PostInfo=PostDispatchInfo>,
+{
+	/// Create the extension with the tip paid when the fee is charged in the native currency.
+	pub fn from(tip: BalanceOf<T>) -> Self {
+		Self(ChargeTransactionPayment::from(tip))
+	}
+}
+
+impl<T: Trait + pallet_transaction_payment::Trait + Send + Sync> sp_std::fmt::Debug for ChargeNotaryCredits<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "ChargeNotaryCredits<{:?}>", self.0)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait + pallet_transaction_payment::Trait + Send + Sync> Module<T> where
+	<T as frame_system::Trait>::Call: IsSubType<Module<T>, T>,
+{
+	/// Whether `who` can pay for `call` with a notary credit.
+	pub fn can_pay_with_credits(who: &T::AccountId, call: &<T as frame_system::Trait>::Call) -> bool {
+		call.is_sub_type().is_some() && Self::notary_credits(who) > 0
+	}
+}
+
+impl<T: Trait + pallet_transaction_payment::Trait + Send + Sync> SignedExtension for ChargeNotaryCredits<T> where
+	BalanceOf<T>: Send + Sync + From<u64>,
+	<T as frame_system::Trait>::Call: Dispatchable<Info=DispatchInfo, PostInfo=PostDispatchInfo>
+		+ IsSubType<Module<T>, T>,
+{
+	const IDENTIFIER: &'static str = "ChargeNotaryCredits";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Trait>::Call;
+	type AdditionalSigned = ();
+	/// `None` when the transaction was paid with a credit, otherwise the fee payment of the
+	/// wrapped extension, which is needed to refund unused weight.
+	type Pre = Option<<ChargeTransactionPayment<T> as SignedExtension>::Pre>;
+
+	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		if Module::<T>::can_pay_with_credits(who, call) {
+			return Ok(ValidTransaction {
+				provides: vec![(Self::IDENTIFIER, who, Module::<T>::notary_credits(who)).encode()],
+				..Default::default()
+			});
+		}
+		self.0.validate(who, call, info, len)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		if Module::<T>::can_pay_with_credits(who, call) {
+			let remaining = <NotaryCredits<T>>::get(who).saturating_sub(1);
+			if remaining == 0 {
+				<NotaryCredits<T>>::remove(who);
+			} else {
+				<NotaryCredits<T>>::insert(who, remaining);
+			}
+			return Ok(None);
+		}
+		self.0.pre_dispatch(who, call, info, len).map(Some)
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		match pre {
+			Some(pre) => ChargeTransactionPayment::<T>::post_dispatch(pre, info, post_info, len, result),
+			None => Ok(()),
+		}
+	}
+}
+
+/// Outermost fee extension of the runtime's `SignedExtra`, wrapping `ChargeNotaryCredits`.
+///
+/// Every account, even one without any balance, may make one `do_something` call per
+/// `FreeCallWindow` without paying anything, so new users can start without buying tokens or
+/// credits. As fresh accounts cost nothing, all accounts together get at most
+/// `MaxFreeCallsPerWindow` free calls per window, which bounds the block space spent on them.
+/// Once the free call of the current window is used, or the window has none left, calls are
+/// paid with notary credits or the normal fee.
+///
+/// The free call is checked against `LastFreeCall` and `FreeCallsInWindow` already in
+/// `validate`, and a valid free call provides a tag unique to the account and window. The pool
+/// therefore rejects further free calls of an account in the same window instead of queueing
+/// them, and rejects free calls from every account once the window's free calls are used up.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct FreeFirstCall<T: Trait + pallet_transaction_payment::Trait + Send + Sync>(
+	ChargeNotaryCredits<T>,
+);
+
+impl<T: Trait + pallet_transaction_payment::Trait + Send + Sync> FreeFirstCall<T> where
+	BalanceOf<T>: Send + Sync,
+	<T as frame_system::Trait>::Call: Dispatchable<Info=DispatchInfo, PostInfo=PostDispatchInfo>,
+{
+	/// Create the extension with the tip paid when the fee is charged in the native currency.
+	pub fn from(tip: BalanceOf<T>) -> Self {
+		Self(ChargeNotaryCredits::from(tip))
+	}
+}
+
+impl<T: Trait + pallet_transaction_payment::Trait + Send + Sync> sp_std::fmt::Debug for FreeFirstCall<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "FreeFirstCall<{:?}>", self.0)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait + pallet_transaction_payment::Trait + Send + Sync> Module<T> where
+	<T as frame_system::Trait>::Call: IsSubType<Module<T>, T>,
+{
+	/// The current free call window if `call` is eligible for a free call and `who` has not
+	/// used it yet.
+	///
+	/// Only `do_something` is eligible, as it is this pallet's call for storing a value.
+	pub fn free_call_window(who: &T::AccountId, call: &<T as frame_system::Trait>::Call) -> Option<T::BlockNumber> {
+		match call.is_sub_type() {
+			Some(Call::do_something(..)) => Self::free_call_available(who),
+			_ => None,
+		}
+	}
+}
+
+impl<T: Trait + pallet_transaction_payment::Trait + Send + Sync> SignedExtension for FreeFirstCall<T> where
+	BalanceOf<T>: Send + Sync + From<u64>,
+	<T as frame_system::Trait>::Call: Dispatchable<Info=DispatchInfo, PostInfo=PostDispatchInfo>
+		+ IsSubType<Module<T>, T>,
+{
+	const IDENTIFIER: &'static str = "FreeFirstCall";
+	type AccountId = T::AccountId;
+	type Call = <T as frame_system::Trait>::Call;
+	type AdditionalSigned = ();
+	/// `None` for a free call, otherwise the payment of the wrapped extension.
+	type Pre = Option<<ChargeNotaryCredits<T> as SignedExtension>::Pre>;
+
+	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		if let Some(window) = Module::<T>::free_call_window(who, call) {
+			// The free call is only valid until its window ends.
+			let window_end = window.saturating_add(One::one()).saturating_mul(T::FreeCallWindow::get());
+			let longevity = window_end
+				.saturating_sub(<frame_system::Module<T>>::block_number())
+				.unique_saturated_into();
+			return Ok(ValidTransaction {
+				provides: vec![(Self::IDENTIFIER, who, window).encode()],
+				longevity,
+				..Default::default()
+			});
+		}
+		self.0.validate(who, call, info, len)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		if let Some(window) = Module::<T>::free_call_window(who, call) {
+			Module::<T>::use_free_call(who, window);
+			return Ok(None);
+		}
+		self.0.pre_dispatch(who, call, info, len).map(Some)
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		match pre {
+			Some(pre) => ChargeNotaryCredits::<T>::post_dispatch(pre, info, post_info, len, result),
+			None => Ok(()),
+		}
+	}
+}
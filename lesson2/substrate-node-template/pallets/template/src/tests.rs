@@ -1,7 +1,19 @@
 // Tests to be written here
 
-use crate::{Error, mock::*};
-use frame_support::{assert_ok, assert_noop};
+use crate::{
+	ChargeNotaryCredits, Error, FreeFirstCall, NotaryCredits, RawEvent, Releases, Something,
+	StorageVersion, StoredValue, migrations, mock::*,
+};
+use codec::Encode;
+use frame_support::{
+	assert_ok, assert_noop, storage::{unhashed, StorageMap, StorageValue},
+	traits::{OnInitialize, schedule::Named as ScheduleNamed},
+	weights::DispatchInfo,
+};
+use sp_runtime::{
+	traits::{BadOrigin, SignedExtension},
+	transaction_validity::InvalidTransaction,
+};
 
 #[test]
 fn it_works_for_default_value() {
@@ -24,3 +36,453 @@ fn correct_error_for_none_value() {
 		);
 	});
 }
+
+#[test]
+fn set_notary_credits_requires_root() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			TemplateModule::set_notary_credits(Origin::signed(1), 2, 5),
+			BadOrigin
+		);
+		assert_ok!(TemplateModule::set_notary_credits(Origin::ROOT, 2, 5));
+		assert_eq!(TemplateModule::notary_credits(2), 5);
+
+		assert_ok!(TemplateModule::set_notary_credits(Origin::ROOT, 2, 0));
+		assert!(!NotaryCredits::<Test>::contains_key(2));
+	});
+}
+
+fn template_call() -> Call {
+	Call::TemplateModule(crate::Call::do_something(42))
+}
+
+fn transfer_call() -> Call {
+	Call::Balances(pallet_balances::Call::transfer(2, 10))
+}
+
+fn info() -> DispatchInfo {
+	DispatchInfo { weight: 10, ..Default::default() }
+}
+
+#[test]
+fn template_call_is_paid_with_credit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::set_notary_credits(Origin::ROOT, 2, 2));
+
+		// Account 2 has no native balance at all.
+		let ext = ChargeNotaryCredits::<Test>::from(0);
+		assert_ok!(ext.validate(&2, &template_call(), &info(), 10));
+		let pre = ext.pre_dispatch(&2, &template_call(), &info(), 10).unwrap();
+		assert!(pre.is_none());
+		assert_eq!(TemplateModule::notary_credits(2), 1);
+		assert_ok!(ChargeNotaryCredits::<Test>::post_dispatch(
+			pre, &info(), &Default::default(), 10, &Ok(())
+		));
+		assert_eq!(Balances::free_balance(2), 0);
+
+		// The last credit removes the entry.
+		let ext = ChargeNotaryCredits::<Test>::from(0);
+		assert!(ext.pre_dispatch(&2, &template_call(), &info(), 10).unwrap().is_none());
+		assert!(!NotaryCredits::<Test>::contains_key(2));
+	});
+}
+
+#[test]
+fn one_pending_credit_call_per_account() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::set_notary_credits(Origin::ROOT, 2, 2));
+
+		// A second transaction validated against the same credits provides the same tag, so the
+		// pool does not queue it next to the first.
+		let ext = ChargeNotaryCredits::<Test>::from(0);
+		let first = ext.validate(&2, &template_call(), &info(), 10).unwrap();
+		let second = ext.validate(&2, &template_call(), &info(), 10).unwrap();
+		assert_eq!(first.provides, vec![("ChargeNotaryCredits", 2u64, 2u32).encode()]);
+		assert_eq!(first.provides, second.provides);
+
+		// Once a credit is spent, the next transaction can enter the pool.
+		assert!(ext.clone().pre_dispatch(&2, &template_call(), &info(), 10).unwrap().is_none());
+		let next = ext.validate(&2, &template_call(), &info(), 10).unwrap();
+		assert_ne!(next.provides, first.provides);
+	});
+}
+
+#[test]
+fn template_call_without_credits_pays_fee() {
+	new_test_ext().execute_with(|| {
+		let ext = ChargeNotaryCredits::<Test>::from(0);
+		assert!(ext.pre_dispatch(&1, &template_call(), &info(), 10).unwrap().is_some());
+		// 10 bytes at one unit each plus a weight of 10.
+		assert_eq!(Balances::free_balance(1), 80);
+
+		// Without credits or native balance the transaction is invalid.
+		let ext = ChargeNotaryCredits::<Test>::from(0);
+		assert_eq!(
+			ext.validate(&2, &template_call(), &info(), 10),
+			Err(InvalidTransaction::Payment.into()),
+		);
+	});
+}
+
+#[test]
+fn other_calls_pay_fee_despite_credits() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::set_notary_credits(Origin::ROOT, 1, 3));
+		assert_ok!(TemplateModule::set_notary_credits(Origin::ROOT, 2, 3));
+
+		let ext = ChargeNotaryCredits::<Test>::from(0);
+		assert!(ext.pre_dispatch(&1, &transfer_call(), &info(), 10).unwrap().is_some());
+		assert_eq!(Balances::free_balance(1), 80);
+		assert_eq!(TemplateModule::notary_credits(1), 3);
+
+		let ext = ChargeNotaryCredits::<Test>::from(0);
+		assert_eq!(
+			ext.validate(&2, &transfer_call(), &info(), 10),
+			Err(InvalidTransaction::Payment.into()),
+		);
+	});
+}
+
+#[test]
+fn one_free_call_per_window() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(3);
+
+		// Account 4 has no credits and only the existential deposit, which cannot pay a fee.
+		let ext = FreeFirstCall::<Test>::from(0);
+		let valid = ext.validate(&4, &template_call(), &info(), 10).unwrap();
+		assert_eq!(valid.provides, vec![("FreeFirstCall", 4u64, 0u64).encode()]);
+		assert_eq!(valid.longevity, 7);
+		assert!(ext.pre_dispatch(&4, &template_call(), &info(), 10).unwrap().is_none());
+		assert_eq!(TemplateModule::last_free_call(4), Some(0));
+
+		// The free call of this window is used up.
+		let ext = FreeFirstCall::<Test>::from(0);
+		assert_eq!(
+			ext.validate(&4, &template_call(), &info(), 10),
+			Err(InvalidTransaction::Payment.into()),
+		);
+
+		// The next window grants a new one.
+		System::set_block_number(10);
+		assert_ok!(ext.validate(&4, &template_call(), &info(), 10));
+	});
+}
+
+#[test]
+fn unfunded_account_makes_its_first_call_for_free() {
+	new_test_ext().execute_with(|| {
+		// Account 2 holds no balance at all, so it does not even exist yet.
+		assert_eq!(Balances::free_balance(2), 0);
+
+		let ext = FreeFirstCall::<Test>::from(0);
+		assert_ok!(ext.validate(&2, &template_call(), &info(), 10));
+		assert!(ext.pre_dispatch(&2, &template_call(), &info(), 10).unwrap().is_none());
+		assert_eq!(TemplateModule::last_free_call(2), Some(0));
+		assert_eq!(Balances::free_balance(2), 0);
+	});
+}
+
+#[test]
+fn free_calls_per_window_are_limited() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(3);
+		for who in &[2, 3] {
+			let ext = FreeFirstCall::<Test>::from(0);
+			assert!(ext.pre_dispatch(who, &template_call(), &info(), 10).unwrap().is_none());
+		}
+		assert_eq!(TemplateModule::free_calls_in_window(), (0, 2));
+
+		// The window's free calls are used up, so account 5 would have to pay.
+		let ext = FreeFirstCall::<Test>::from(0);
+		assert_eq!(
+			ext.validate(&5, &template_call(), &info(), 10),
+			Err(InvalidTransaction::Payment.into()),
+		);
+		assert!(ext.clone().pre_dispatch(&5, &template_call(), &info(), 10).is_err());
+
+		// The next window starts counting from zero, and the stale entries of the last one do
+		// not get in the way.
+		System::set_block_number(10);
+		assert_ok!(ext.validate(&5, &template_call(), &info(), 10));
+		assert_ok!(ext.validate(&2, &template_call(), &info(), 10));
+	});
+}
+
+#[test]
+fn free_call_is_used_before_credits() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::set_notary_credits(Origin::ROOT, 4, 1));
+
+		let ext = FreeFirstCall::<Test>::from(0);
+		assert!(ext.pre_dispatch(&4, &template_call(), &info(), 10).unwrap().is_none());
+		assert_eq!(TemplateModule::notary_credits(4), 1);
+
+		// Afterwards the credit pays.
+		let ext = FreeFirstCall::<Test>::from(0);
+		let pre = ext.pre_dispatch(&4, &template_call(), &info(), 10).unwrap();
+		assert!(pre.unwrap().is_none());
+		assert_eq!(TemplateModule::notary_credits(4), 0);
+	});
+}
+
+#[test]
+fn only_do_something_is_free() {
+	new_test_ext().execute_with(|| {
+		let cause_error = Call::TemplateModule(crate::Call::cause_error());
+		let ext = FreeFirstCall::<Test>::from(0);
+		assert_eq!(
+			ext.validate(&4, &cause_error, &info(), 10),
+			Err(InvalidTransaction::Payment.into()),
+		);
+		assert_eq!(
+			ext.validate(&4, &transfer_call(), &info(), 10),
+			Err(InvalidTransaction::Payment.into()),
+		);
+		assert_eq!(TemplateModule::last_free_call(4), None);
+	});
+}
+
+fn store(value: u32) -> Box<Call> {
+	Box::new(Call::TemplateModule(crate::Call::do_something(value)))
+}
+
+#[test]
+fn scheduled_call_is_dispatched_as_owner() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(TemplateModule::schedule_call(Origin::signed(1), 1, 5, store(7)));
+		assert_eq!(TemplateModule::scheduled_calls(1), vec![(0, 5, *store(7))]);
+
+		Scheduler::on_initialize(4);
+		assert_eq!(TemplateModule::something(), None);
+
+		Scheduler::on_initialize(5);
+		assert_eq!(TemplateModule::something(), Some(7));
+		assert!(TemplateModule::scheduled_calls(1).is_empty());
+	});
+}
+
+#[test]
+fn scheduled_call_can_be_cancelled() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(TemplateModule::schedule_call(Origin::signed(1), 1, 5, store(7)));
+		assert_ok!(TemplateModule::schedule_call(Origin::ROOT, 1, 6, store(8)));
+		assert_eq!(TemplateModule::scheduled_calls(1).len(), 2);
+
+		assert_noop!(TemplateModule::cancel_scheduled_call(Origin::signed(2), 1, 0), BadOrigin);
+		assert_ok!(TemplateModule::cancel_scheduled_call(Origin::signed(1), 1, 0));
+		assert_noop!(
+			TemplateModule::cancel_scheduled_call(Origin::signed(1), 1, 0),
+			Error::<Test>::NotScheduled
+		);
+		assert_eq!(TemplateModule::scheduled_calls(1), vec![(1, 6, *store(8))]);
+
+		Scheduler::on_initialize(5);
+		assert_eq!(TemplateModule::something(), None);
+		Scheduler::on_initialize(6);
+		assert_eq!(TemplateModule::something(), Some(8));
+	});
+}
+
+#[test]
+fn failed_cancel_keeps_the_record() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(TemplateModule::schedule_call(Origin::signed(1), 1, 5, store(7)));
+
+		// Remove the task behind the pallet's back, so that cancelling it fails.
+		let id = (crate::SCHEDULE_ID_PREFIX, 1u64, 0u32).encode();
+		assert_ok!(<Scheduler as ScheduleNamed<u64, Call>>::cancel_named(id));
+
+		assert_noop!(
+			TemplateModule::cancel_scheduled_call(Origin::signed(1), 1, 0),
+			Error::<Test>::NotScheduled
+		);
+		assert_eq!(TemplateModule::scheduled_calls(1), vec![(0, 5, *store(7))]);
+	});
+}
+
+#[test]
+fn schedule_call_checks_its_arguments() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(3);
+		assert_noop!(TemplateModule::schedule_call(Origin::signed(2), 1, 5, store(7)), BadOrigin);
+		assert_noop!(
+			TemplateModule::schedule_call(Origin::signed(1), 1, 3, store(7)),
+			Error::<Test>::InPast
+		);
+		assert_noop!(
+			TemplateModule::schedule_call(Origin::signed(1), 1, 5, Box::new(transfer_call())),
+			Error::<Test>::NotTemplateCall
+		);
+
+		assert_ok!(TemplateModule::schedule_call(Origin::signed(1), 1, 5, store(7)));
+		assert_ok!(TemplateModule::schedule_call(Origin::signed(1), 1, 5, store(8)));
+		assert_noop!(
+			TemplateModule::schedule_call(Origin::signed(1), 1, 5, store(9)),
+			Error::<Test>::TooManyScheduled
+		);
+	});
+}
+
+#[test]
+fn genesis_registrars_are_added() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(registrars(), vec![3]);
+	});
+}
+
+#[test]
+fn owners_resolve_to_verified_display_names() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(TemplateModule::owner_display_name(&1), Some(b"Alice".to_vec()));
+		assert_eq!(TemplateModule::owner_display_name(&2), None);
+	});
+}
+
+#[test]
+fn stored_values_identify_verified_owners() {
+	new_test_ext().execute_with(|| {
+		// Events are not recorded in the genesis block.
+		System::set_block_number(1);
+		assert_ok!(TemplateModule::do_something(Origin::signed(1), 42));
+		assert_ok!(TemplateModule::do_something(Origin::signed(2), 43));
+
+		let events: Vec<_> = System::events().into_iter().map(|record| record.event).collect();
+		assert_eq!(events, vec![
+			TestEvent::template(RawEvent::SomethingStored(42, 1)),
+			TestEvent::template(RawEvent::OwnerIdentified(1, b"Alice".to_vec())),
+			TestEvent::template(RawEvent::SomethingStored(43, 2)),
+		]);
+	});
+}
+
+#[test]
+fn stored_value_is_readable_at_its_key() {
+	new_test_ext().execute_with(|| {
+		let key = TemplateModule::stored_value_key();
+		assert_eq!(unhashed::get::<StoredValue<u64>>(&key), None);
+
+		System::set_block_number(3);
+		assert_ok!(TemplateModule::do_something(Origin::signed(1), 42));
+		assert_eq!(
+			unhashed::get::<StoredValue<u64>>(&key),
+			Some(StoredValue { value: 42, updated_at: 3 }),
+		);
+	});
+}
+
+/// Puts the storage into the `V1_0_0` layout with the given value of `Something`.
+fn set_v1_storage(something: Option<u32>) {
+	StorageVersion::kill();
+	match something {
+		Some(value) => unhashed::put(&Something::<Test>::hashed_key(), &value),
+		None => Something::<Test>::kill(),
+	}
+}
+
+#[test]
+fn new_chains_start_at_latest_version() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(StorageVersion::get(), Releases::V2_0_0);
+	});
+}
+
+#[test]
+fn migration_to_v2_wraps_something() {
+	new_test_ext().execute_with(|| {
+		set_v1_storage(Some(42));
+		System::set_block_number(7);
+
+		migrations::migrate::<Test>();
+
+		assert_eq!(StorageVersion::get(), Releases::V2_0_0);
+		assert_eq!(
+			TemplateModule::stored_value(),
+			Some(StoredValue { value: 42, updated_at: 7 })
+		);
+		assert_ok!(TemplateModule::cause_error(Origin::signed(1)));
+		assert_eq!(TemplateModule::something(), Some(43));
+	});
+}
+
+#[test]
+fn migration_to_v2_keeps_empty_value() {
+	new_test_ext().execute_with(|| {
+		set_v1_storage(None);
+
+		migrations::migrate::<Test>();
+
+		assert_eq!(StorageVersion::get(), Releases::V2_0_0);
+		assert_eq!(TemplateModule::stored_value(), None);
+	});
+}
+
+#[test]
+fn post_upgrade_detects_missing_migration() {
+	new_test_ext().execute_with(|| {
+		set_v1_storage(Some(42));
+		assert!(migrations::post_upgrade::<Test>().is_err());
+
+		migrations::migrate::<Test>();
+		assert_ok!(migrations::post_upgrade::<Test>());
+	});
+}
+
+#[test]
+fn migration_runs_only_once() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::do_something(Origin::signed(1), 42));
+		let stored = TemplateModule::stored_value();
+
+		// Already at the latest version, so nothing is touched.
+		migrations::migrate::<Test>();
+		assert_eq!(TemplateModule::stored_value(), stored);
+	});
+}
+
+#[test]
+fn stored_values_can_be_bound_to_a_whole_asset_class() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(TemplateModule::bind_asset(Origin::signed(1), 0), Error::<Test>::NoneValue);
+		assert_ok!(TemplateModule::do_something(Origin::signed(1), 42));
+
+		// Account 2 did not store the value, and nobody holds all units of asset 1.
+		assert_noop!(TemplateModule::bind_asset(Origin::signed(2), 0), Error::<Test>::NotOwner);
+		assert_noop!(TemplateModule::bind_asset(Origin::signed(1), 1), Error::<Test>::NotWholeSupply);
+
+		assert_ok!(TemplateModule::bind_asset(Origin::signed(1), 0));
+		assert_eq!(TemplateModule::bound_asset(), Some(0));
+		assert_noop!(TemplateModule::bind_asset(Origin::signed(1), 0), Error::<Test>::AlreadyBound);
+
+		// A new value is not covered by the binding of the old one.
+		assert_ok!(TemplateModule::do_something(Origin::signed(1), 43));
+		assert_eq!(TemplateModule::bound_asset(), None);
+	});
+}
+
+#[test]
+fn only_the_owner_binds_a_stored_value() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::do_something(Origin::signed(2), 42));
+		assert_eq!(TemplateModule::owner(), Some(2));
+
+		// Account 1 holds all units of asset 0, but did not store the value.
+		assert_noop!(TemplateModule::bind_asset(Origin::signed(1), 0), Error::<Test>::NotOwner);
+	});
+}
+
+#[test]
+fn others_cannot_replace_a_bound_value() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(TemplateModule::do_something(Origin::signed(1), 42));
+		assert_ok!(TemplateModule::bind_asset(Origin::signed(1), 0));
+
+		assert_noop!(TemplateModule::do_something(Origin::signed(2), 43), Error::<Test>::ValueBound);
+		assert_noop!(TemplateModule::cause_error(Origin::signed(2)), Error::<Test>::ValueBound);
+		assert_eq!(TemplateModule::something(), Some(42));
+		assert_eq!(TemplateModule::bound_asset(), Some(0));
+	});
+}
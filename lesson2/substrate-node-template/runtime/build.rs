@@ -3,7 +3,9 @@ use wasm_builder_runner::WasmBuilder;
 fn main() {
 	WasmBuilder::new()
 		.with_current_project()
-		.with_wasm_builder_from_crates("1.0.9")
+		// 1.0.11 is the first wasm builder that builds the Wasm binary with the same features
+		// as the native runtime, which keeps `babe-consensus` in sync between the two.
+		.with_wasm_builder_from_crates("1.0.11")
 		.export_heap_base()
 		.import_memory()
 		.build()
@@ -0,0 +1,776 @@
+//! Some configurable implementations as associated type for the substrate runtime.
+
+use sp_std::prelude::*;
+use sp_core::{H160, H256, U256};
+use sp_runtime::{Fixed128, FixedPointNumber, Perbill, Perquintill, traits::{Convert, Saturating, Zero}};
+use codec::{Decode, Encode};
+use frame_support::{
+	IsSubType, RuntimeDebug, StorageValue,
+	dispatch::{DispatchResultWithPostInfo, Dispatchable},
+	traits::{
+		Contains, ContainsLengthBound, Currency, EnsureOrigin, Filter, Get, Imbalance, OnUnbalanced,
+	},
+	weights::{
+		DispatchInfo, GetDispatchInfo, PostDispatchInfo, WeightToFeeCoefficient,
+		WeightToFeeCoefficients, WeightToFeePolynomial,
+	},
+};
+use smallvec::smallvec;
+use ethereum_evm::{ExitError, ExitSucceed, backend::Backend as _, executor::StackExecutor};
+use evm::{FeeCalculator, Precompiles};
+use crate::{
+	AccountId, Assets, Authorship, Balance, Balances, Call, Council, Identity, MaximumBlockWeight,
+	MaxRegistrars, MaxTippers, Origin, Runtime, System, TemplateModule, Treasury, TreasuryFeeShare,
+};
+
+type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
+
+/// Credits the block author with the given amount.
+pub struct Author;
+
+impl OnUnbalanced<NegativeImbalance> for Author {
+	fn on_nonzero_unbalanced(amount: NegativeImbalance) {
+		Balances::resolve_creating(&Authorship::author(), amount);
+	}
+}
+
+/// Pays `TreasuryFeeShare` of every transaction fee into the treasury and the rest to the
+/// block author, instead of burning it.
+pub struct DealWithFees;
+
+impl OnUnbalanced<NegativeImbalance> for DealWithFees {
+	fn on_nonzero_unbalanced(fees: NegativeImbalance) {
+		let to_treasury = TreasuryFeeShare::get() * fees.peek();
+		let (to_treasury, to_author) = fees.split(to_treasury);
+		Treasury::on_unbalanced(to_treasury);
+		Author::on_unbalanced(to_author);
+	}
+}
+
+/// The council members, who are allowed to tip from the treasury.
+pub struct CouncilTippers;
+
+impl Contains<AccountId> for CouncilTippers {
+	fn sorted_members() -> Vec<AccountId> {
+		let mut members = Council::members();
+		members.sort();
+		members
+	}
+}
+
+impl ContainsLengthBound for CouncilTippers {
+	fn min_len() -> usize {
+		0
+	}
+
+	fn max_len() -> usize {
+		MaxTippers::get() as usize
+	}
+}
+
+/// Accepts an origin that passes either `L` or `R`. Stands in for `EnsureOneOf`, which
+/// `frame_system` does not have yet.
+pub struct EnsureOneOf<L, R>(sp_std::marker::PhantomData<(L, R)>);
+
+impl<O, L: EnsureOrigin<O>, R: EnsureOrigin<O>> EnsureOrigin<O> for EnsureOneOf<L, R> {
+	type Success = ();
+
+	fn try_origin(o: O) -> Result<(), O> {
+		L::try_origin(o).map(|_| ()).or_else(|o| R::try_origin(o).map(|_| ()))
+	}
+}
+
+/// Resolves accounts to the display names of their identities, provided a registrar judged
+/// them `Reasonable` or `KnownGood`.
+pub struct VerifiedIdentity;
+
+impl template::IdentityProvider<AccountId> for VerifiedIdentity {
+	fn verified_display_name(who: &AccountId) -> Option<Vec<u8>> {
+		let registration = Identity::identity(who)?;
+		let verified = registration.judgements.iter().any(|(_, judgement)| matches!(judgement,
+			identity::Judgement::Reasonable | identity::Judgement::KnownGood
+		));
+		match registration.info.display {
+			identity::Data::Raw(name) if verified => Some(name),
+			_ => None,
+		}
+	}
+
+	fn add_registrar(who: AccountId) -> Result<(), &'static str> {
+		// Written directly rather than dispatched, so that genesis records no events.
+		identity::Registrars::<Runtime>::try_mutate(|registrars| {
+			if registrars.len() >= MaxRegistrars::get() as usize {
+				return Err("Too many identity registrars");
+			}
+			registrars.push(Some(identity::RegistrarInfo { account: who, fee: 0, fields: Default::default() }));
+			Ok(())
+		})
+	}
+}
+
+/// Lets the template pallet bind stored values to asset classes of `pallet-assets`.
+pub struct ShareAssets;
+
+impl template::AssetProvider<AccountId, u32> for ShareAssets {
+	fn holds_all_of(who: &AccountId, id: u32) -> bool {
+		let supply = Assets::total_supply(id);
+		!supply.is_zero() && Assets::balance(id, who.clone()) == supply
+	}
+}
+
+/// The calls contracts may dispatch with `ext_dispatch_call`. Only template pallet calls
+/// decode, so a contract cannot reach balances, democracy or any other pallet with the
+/// authority of its own account.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+pub struct ContractCall(Call);
+
+impl Decode for ContractCall {
+	fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+		match Call::decode(input)? {
+			call @ Call::TemplateModule(_) => Ok(ContractCall(call)),
+			_ => Err("contracts may only dispatch template pallet calls".into()),
+		}
+	}
+}
+
+impl Dispatchable for ContractCall {
+	type Origin = Origin;
+	type Trait = Runtime;
+	type Info = DispatchInfo;
+	type PostInfo = PostDispatchInfo;
+
+	fn dispatch(self, origin: Origin) -> DispatchResultWithPostInfo {
+		self.0.dispatch(origin)
+	}
+}
+
+impl GetDispatchInfo for ContractCall {
+	fn get_dispatch_info(&self) -> DispatchInfo {
+		self.0.get_dispatch_info()
+	}
+}
+
+impl IsSubType<contracts::Module<Runtime>, Runtime> for ContractCall {
+	fn is_sub_type(&self) -> Option<&contracts::Call<Runtime>> {
+		None
+	}
+}
+
+/// The calls the utility pallet dispatches in signed batches and for sub-accounts and multisig
+/// accounts: notarizations with the template pallet's `do_something`, alone or batched.
+///
+/// This makes a multisig account with a threshold of 1 a notarization proxy. A cold key and a
+/// hot key share such an account, and either can store values as it with `as_multi`, but
+/// neither can move its funds, bond, vote or dispatch anything else through it. The cold key's
+/// own account is not affected.
+pub struct NotarizationOnly;
+
+impl Filter<Call> for NotarizationOnly {
+	fn filter(call: &Call) -> bool {
+		match call {
+			Call::TemplateModule(template::Call::do_something(..)) => true,
+			Call::Utility(utility::Call::batch(calls)) => calls.iter().all(Self::filter),
+			_ => false,
+		}
+	}
+}
+
+/// Struct that handles the conversion of Balance -> `u64`. This is used for staking's election
+/// calculation.
+pub struct CurrencyToVoteHandler;
+
+impl CurrencyToVoteHandler {
+	fn factor() -> Balance { (Balances::total_issuance() / u64::max_value() as Balance).max(1) }
+}
+
+impl Convert<Balance, u64> for CurrencyToVoteHandler {
+	fn convert(x: Balance) -> u64 { (x / Self::factor()) as u64 }
+}
+
+impl Convert<u128, Balance> for CurrencyToVoteHandler {
+	fn convert(x: u128) -> Balance { x * Self::factor() }
+}
+
+/// The minimum gas price of EVM transactions. The actual fee is paid in the native currency.
+pub struct FixedGasPrice;
+
+impl FeeCalculator for FixedGasPrice {
+	fn min_gas_price() -> U256 {
+		1.into()
+	}
+}
+
+/// Address of the precompile reaching the template pallet, after the Ethereum builtins.
+pub const TEMPLATE_PRECOMPILE: H160 = H160([
+	0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x04, 0x00,
+]);
+
+/// Gas charged for reading through `TEMPLATE_PRECOMPILE`, about one storage read.
+const TEMPLATE_PRECOMPILE_GAS: usize = 1_000;
+
+/// The precompiles of the EVM, letting Solidity contracts use the template pallet.
+///
+/// A call to `TEMPLATE_PRECOMPILE` with empty input returns the ABI encoding of
+/// `(bool isSet, uint256 something)`. The precompile is read-only: it writes outside of the
+/// EVM's state, so a write would survive a revert of the calling contract, and it cannot tell
+/// which account called it. Any other input fails the call.
+pub struct TemplatePrecompiles;
+
+impl Precompiles for TemplatePrecompiles {
+	fn execute(
+		address: H160,
+		input: &[u8],
+		target_gas: Option<usize>,
+	) -> Option<Result<(ExitSucceed, Vec<u8>, usize), ExitError>> {
+		if address != TEMPLATE_PRECOMPILE {
+			return None;
+		}
+		if target_gas.map_or(false, |gas| gas < TEMPLATE_PRECOMPILE_GAS) {
+			return Some(Err(ExitError::OutOfGas));
+		}
+		if !input.is_empty() {
+			return Some(Err(ExitError::Other("the template precompile takes no input")));
+		}
+
+		let mut output = vec![0u8; 64];
+		if let Some(something) = TemplateModule::something() {
+			output[31] = 1;
+			U256::from(something).to_big_endian(&mut output[32..]);
+		}
+		Some(Ok((ExitSucceed::Returned, output, TEMPLATE_PRECOMPILE_GAS)))
+	}
+}
+
+/// The code of the EVM contract at `address`, for `EvmRuntimeApi::account_code`.
+pub fn evm_account_code(address: H160) -> Vec<u8> {
+	with_evm_backend(|backend| backend.code(address))
+}
+
+/// Slot `index` of the EVM contract at `address`, for `EvmRuntimeApi::storage_at`.
+pub fn evm_storage_at(address: H160, index: H256) -> H256 {
+	with_evm_backend(|backend| backend.storage(address, index))
+}
+
+/// Reads the EVM state through the backend the EVM itself executes against.
+fn with_evm_backend<R>(f: impl FnOnce(&evm::Backend<Runtime>) -> R) -> R {
+	let vicinity = evm::Vicinity { gas_price: U256::zero(), origin: H160::zero() };
+	f(&evm::Backend::<Runtime>::new(&vicinity))
+}
+
+/// Dry-runs an EVM call for `EvmRuntimeApi::call`. Returns whether the call succeeded and the
+/// gas it used. No fee is charged, and the state changes are dropped with the runtime API call.
+pub fn dry_run_evm_call(
+	from: H160,
+	to: H160,
+	data: Vec<u8>,
+	value: U256,
+	gas_limit: u32,
+) -> (bool, U256) {
+	let vicinity = evm::Vicinity { gas_price: FixedGasPrice::min_gas_price(), origin: from };
+	let backend = evm::Backend::<Runtime>::new(&vicinity);
+	let mut executor = StackExecutor::new_with_precompile(
+		&backend,
+		gas_limit as usize,
+		<Runtime as evm::Trait>::config(),
+		TemplatePrecompiles::execute,
+	);
+	let reason = executor.transact_call(from, to, value, data, gas_limit as usize);
+	(reason.is_succeed(), U256::from(gas_limit as usize - executor.gas()))
+}
+
+/// The current Aura authorities, in the order slots are assigned to them.
+#[cfg(not(feature = "babe-consensus"))]
+pub struct AuraAuthorities;
+
+#[cfg(not(feature = "babe-consensus"))]
+impl Get<Vec<sp_consensus_aura::sr25519::AuthorityId>> for AuraAuthorities {
+	fn get() -> Vec<sp_consensus_aura::sr25519::AuthorityId> {
+		crate::Aura::authorities()
+	}
+}
+
+/// Converts weight to fee along a quadratic curve.
+///
+/// The linear term charges one unit per unit of weight, like `IdentityFee`. The quadratic term
+/// adds about 12% for an extrinsic of `ExtrinsicBaseWeight`, and makes an extrinsic that fills
+/// a whole block about two thousand times more expensive than its linear fee.
+pub struct WeightToFee;
+
+impl WeightToFeePolynomial for WeightToFee {
+	type Balance = Balance;
+
+	fn polynomial() -> WeightToFeeCoefficients<Self::Balance> {
+		smallvec![
+			WeightToFeeCoefficient {
+				coeff_integer: 1,
+				coeff_frac: Perbill::zero(),
+				negative: false,
+				degree: 1,
+			},
+			WeightToFeeCoefficient {
+				coeff_integer: 0,
+				coeff_frac: Perbill::from_parts(1),
+				negative: false,
+				degree: 2,
+			},
+		]
+	}
+}
+
+/// Updates the fee multiplier from the weight of the block that was just built.
+///
+/// With `f = 1 + multiplier` being the factor applied to the weight fee:
+///
+///   diff = (block_weight - target_weight) / max_weight
+///   next_f = f * (1 + v * diff + (v * diff)^2 / 2)
+///
+/// where `target_weight` is `T` of `MaximumBlockWeight` and `v` is given by `V`. Fees therefore
+/// grow exponentially while blocks are fuller than the target and decay back once they are
+/// not. The multiplier never drops below zero, so fees never go below the base fee.
+pub struct TargetedFeeAdjustment<T, V>(sp_std::marker::PhantomData<(T, V)>);
+
+impl<T: Get<Perquintill>, V: Get<Fixed128>> Convert<Fixed128, Fixed128> for TargetedFeeAdjustment<T, V> {
+	fn convert(multiplier: Fixed128) -> Fixed128 {
+		let max_weight = MaximumBlockWeight::get();
+		let block_weight = System::block_weight().total().min(max_weight) as u128;
+		let target_weight = (T::get() * max_weight) as u128;
+
+		// determines whether fees should grow or decay
+		let positive = block_weight >= target_weight;
+		let diff_abs = block_weight.max(target_weight) - block_weight.min(target_weight);
+		// safe, `diff_abs` cannot exceed `max_weight`.
+		let diff = Fixed128::saturating_from_rational(diff_abs, max_weight.max(1) as u128);
+
+		let first_term = V::get().saturating_mul(diff);
+		let second_term = first_term.saturating_mul(first_term)
+			.saturating_mul(Fixed128::saturating_from_rational(1, 2));
+
+		let one = Fixed128::one();
+		let factor = if positive {
+			one.saturating_add(first_term).saturating_add(second_term)
+		} else {
+			one.saturating_sub(first_term).saturating_add(second_term)
+		};
+
+		let fee_factor = multiplier.saturating_add(one);
+		fee_factor.saturating_mul(factor)
+			.saturating_sub(one)
+			.max(Fixed128::zero())
+	}
+}
+
+#[cfg(test)]
+mod multiplier_tests {
+	use super::*;
+	use frame_support::weights::Weight;
+	use crate::{
+		AdjustmentVariable, AvailableBlockRatio, MaximumBlockWeight, Runtime, TargetBlockFullness,
+	};
+
+	type Update = TargetedFeeAdjustment<TargetBlockFullness, AdjustmentVariable>;
+
+	/// The weight of a block filled with normal extrinsics.
+	fn full() -> Weight {
+		AvailableBlockRatio::get() * MaximumBlockWeight::get()
+	}
+
+	fn target() -> Weight {
+		TargetBlockFullness::get() * MaximumBlockWeight::get()
+	}
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		system::GenesisConfig::default().build_storage::<Runtime>().unwrap().into()
+	}
+
+	/// Runs `blocks` blocks of the given weight through the multiplier update, the same way
+	/// transaction payment does when each of them is finalized.
+	fn run_blocks(start: Fixed128, weight: Weight, blocks: usize) -> Vec<Fixed128> {
+		let mut multiplier = start;
+		let mut history = Vec::with_capacity(blocks);
+		new_test_ext().execute_with(|| {
+			System::set_block_limits(weight, 0);
+			for _ in 0..blocks {
+				multiplier = Update::convert(multiplier);
+				history.push(multiplier);
+			}
+		});
+		history
+	}
+
+	#[test]
+	fn multiplier_is_stable_at_target() {
+		let history = run_blocks(Fixed128::one(), target(), 100);
+		assert!(history.iter().all(|m| *m == Fixed128::one()));
+	}
+
+	#[test]
+	fn multiplier_stays_at_floor_on_empty_blocks() {
+		let history = run_blocks(Fixed128::zero(), 0, 100);
+		assert!(history.iter().all(|m| *m == Fixed128::zero()));
+	}
+
+	#[test]
+	fn multiplier_grows_under_sustained_load() {
+		let history = run_blocks(Fixed128::zero(), full(), 1_000);
+
+		assert!(history.windows(2).all(|w| w[1] > w[0]));
+		// (1 + 0.00005 + 0.00005^2 / 2)^1000 - 1 ~= 0.0513
+		let last = *history.last().unwrap();
+		assert!(last > Fixed128::saturating_from_rational(5, 100));
+		assert!(last < Fixed128::saturating_from_rational(52, 1_000));
+	}
+
+	#[test]
+	fn multiplier_growth_accelerates() {
+		// Fees grow exponentially, so the second thousand full blocks add more than the first.
+		let history = run_blocks(Fixed128::zero(), full(), 2_000);
+		let first = history[999];
+		let second = history[1_999].saturating_sub(first);
+		assert!(second > first);
+	}
+
+	#[test]
+	fn multiplier_decays_back_when_load_stops() {
+		// Start with fees doubled and produce empty blocks until they are back to normal.
+		let history = run_blocks(Fixed128::one(), 0, 30_000);
+
+		assert!(history.windows(2).all(|w| w[1] <= w[0]));
+		assert_eq!(*history.last().unwrap(), Fixed128::zero());
+		// ln(2) / 0.000025 ~= 27_726 blocks are needed to halve the fee factor.
+		assert!(history[27_000] > Fixed128::zero());
+	}
+
+	#[test]
+	fn multiplier_does_not_overflow_on_max_weight() {
+		let history = run_blocks(Fixed128::saturating_from_integer(i128::max_value() / 2), Weight::max_value(), 10);
+		assert!(history.iter().all(|m| *m >= Fixed128::zero()));
+	}
+
+	#[test]
+	fn weight_to_fee_is_quadratic() {
+		assert_eq!(WeightToFee::calc(&0), 0);
+		assert_eq!(WeightToFee::calc(&1_000), 1_000);
+		assert_eq!(WeightToFee::calc(&1_000_000_000), 2_000_000_000);
+		assert_eq!(WeightToFee::calc(&2_000_000_000), 6_000_000_000);
+		// A full block costs about 2000 times its linear fee.
+		assert_eq!(WeightToFee::calc(&MaximumBlockWeight::get()), 2_001 * MaximumBlockWeight::get() as Balance);
+	}
+
+	#[test]
+	fn weight_to_fee_does_not_overflow() {
+		let fee = WeightToFee::calc(&Weight::max_value());
+		assert!(fee < Balance::max_value());
+		assert!(fee > Balance::from(Weight::max_value()));
+	}
+}
+
+#[cfg(test)]
+mod precompile_tests {
+	use super::*;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		system::GenesisConfig::default().build_storage::<Runtime>().unwrap().into()
+	}
+
+	#[test]
+	fn template_precompile_returns_something() {
+		new_test_ext().execute_with(|| {
+			let (_, output, _) = TemplatePrecompiles::execute(TEMPLATE_PRECOMPILE, &[], None)
+				.unwrap()
+				.unwrap();
+			assert_eq!(output, vec![0u8; 64]);
+
+			TemplateModule::do_something(Origin::signed(Default::default()), 42).unwrap();
+			let (_, output, gas) = TemplatePrecompiles::execute(TEMPLATE_PRECOMPILE, &[], None)
+				.unwrap()
+				.unwrap();
+			assert_eq!(output[31], 1);
+			assert_eq!(U256::from_big_endian(&output[32..]), U256::from(42));
+			assert_eq!(gas, TEMPLATE_PRECOMPILE_GAS);
+		});
+	}
+
+	#[test]
+	fn other_addresses_are_not_precompiles() {
+		assert!(TemplatePrecompiles::execute(H160::repeat_byte(1), &[], None).is_none());
+	}
+
+	#[test]
+	fn template_precompile_is_read_only() {
+		new_test_ext().execute_with(|| {
+			let mut input = [0u8; 32];
+			U256::from(42).to_big_endian(&mut input);
+			assert!(matches!(
+				TemplatePrecompiles::execute(TEMPLATE_PRECOMPILE, &input, None),
+				Some(Err(ExitError::Other(_)))
+			));
+			assert_eq!(TemplateModule::something(), None);
+		});
+	}
+
+	#[test]
+	fn reverted_calls_leave_something_unchanged() {
+		// Calls the precompile with the value 42, then reverts.
+		let mut code = vec![
+			0x60, 42, 0x60, 0, 0x52, // MSTORE(0, 42)
+			0x60, 0, 0x60, 0, 0x60, 32, 0x60, 0, 0x60, 0, 0x73, // return data, input, value
+		];
+		code.extend_from_slice(TEMPLATE_PRECOMPILE.as_bytes());
+		code.extend_from_slice(&[
+			0x5a, 0xf1, 0x50, // POP(CALL(GAS, TEMPLATE_PRECOMPILE, ...))
+			0x60, 0, 0x60, 0, 0xfd, // REVERT(0, 0)
+		]);
+		let contract = H160::repeat_byte(2);
+		let mut t = system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		evm::GenesisConfig {
+			accounts: vec![(contract, evm::GenesisAccount {
+				nonce: U256::zero(),
+				balance: U256::zero(),
+				storage: Default::default(),
+				code,
+			})].into_iter().collect(),
+		}.assimilate_storage(&mut t).unwrap();
+
+		sp_io::TestExternalities::from(t).execute_with(|| {
+			TemplateModule::do_something(Origin::signed(Default::default()), 7).unwrap();
+			let (succeeded, _) =
+				dry_run_evm_call(H160::repeat_byte(1), contract, vec![], U256::zero(), 100_000);
+			assert!(!succeeded);
+			assert_eq!(TemplateModule::something(), Some(7));
+		});
+	}
+
+	#[test]
+	fn dry_run_reaches_the_precompile() {
+		new_test_ext().execute_with(|| {
+			let from = H160::repeat_byte(1);
+			let (succeeded, used_gas) =
+				dry_run_evm_call(from, TEMPLATE_PRECOMPILE, vec![], U256::zero(), 100_000);
+			assert!(succeeded);
+			assert!(used_gas >= U256::from(TEMPLATE_PRECOMPILE_GAS));
+
+			// Not even enough gas for the transaction itself.
+			let (succeeded, _) = dry_run_evm_call(from, TEMPLATE_PRECOMPILE, vec![], U256::zero(), 100);
+			assert!(!succeeded);
+		});
+	}
+
+	#[test]
+	fn template_precompile_runs_out_of_gas() {
+		new_test_ext().execute_with(|| {
+			assert!(matches!(
+				TemplatePrecompiles::execute(TEMPLATE_PRECOMPILE, &[], Some(10)),
+				Some(Err(ExitError::OutOfGas))
+			));
+		});
+	}
+}
+
+
+#[cfg(test)]
+mod contract_call_tests {
+	use super::*;
+	use crate::BalancesCall;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		system::GenesisConfig::default().build_storage::<Runtime>().unwrap().into()
+	}
+
+	#[test]
+	fn template_calls_decode() {
+		let call = Call::TemplateModule(template::Call::do_something(42));
+		let decoded = ContractCall::decode(&mut &call.encode()[..]).unwrap();
+		assert_eq!(decoded, ContractCall(call.clone()));
+		assert_eq!(decoded.encode(), call.encode());
+	}
+
+	#[test]
+	fn other_calls_do_not_decode() {
+		let transfer = Call::Balances(BalancesCall::transfer(Default::default(), 1));
+		assert!(ContractCall::decode(&mut &transfer.encode()[..]).is_err());
+
+		let remark = Call::System(system::Call::remark(vec![]));
+		assert!(ContractCall::decode(&mut &remark.encode()[..]).is_err());
+	}
+
+	#[test]
+	fn template_calls_dispatch_as_the_contract() {
+		new_test_ext().execute_with(|| {
+			let call = ContractCall(Call::TemplateModule(template::Call::do_something(42)));
+			assert!(call.dispatch(Origin::signed(Default::default())).is_ok());
+			assert_eq!(TemplateModule::something(), Some(42));
+		});
+	}
+}
+
+#[cfg(test)]
+mod notarization_filter_tests {
+	use super::*;
+	use crate::BalancesCall;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		system::GenesisConfig::default().build_storage::<Runtime>().unwrap().into()
+	}
+
+	fn notarize(value: u32) -> Call {
+		Call::TemplateModule(template::Call::do_something(value))
+	}
+
+	#[test]
+	fn only_notarizations_pass() {
+		let transfer = Call::Balances(BalancesCall::transfer(Default::default(), 1));
+		let credits = Call::TemplateModule(template::Call::set_notary_credits(Default::default(), 1));
+
+		assert!(NotarizationOnly::filter(&notarize(42)));
+		assert!(NotarizationOnly::filter(&Call::Utility(utility::Call::batch(vec![notarize(1), notarize(2)]))));
+		assert!(!NotarizationOnly::filter(&transfer));
+		assert!(!NotarizationOnly::filter(&credits));
+		assert!(!NotarizationOnly::filter(&Call::Utility(utility::Call::batch(vec![notarize(1), transfer]))));
+		assert!(!NotarizationOnly::filter(&Call::Utility(utility::Call::as_sub(0, Box::new(notarize(1))))));
+	}
+
+	#[test]
+	fn hot_key_notarizes_through_a_shared_multisig() {
+		new_test_ext().execute_with(|| {
+			let cold = AccountId::from([1u8; 32]);
+			let hot = AccountId::from([2u8; 32]);
+			let as_notary = |call: Call| Call::Utility(utility::Call::as_multi(1, vec![cold.clone()], None, Box::new(call)))
+				.dispatch(Origin::signed(hot.clone()));
+
+			assert!(as_notary(notarize(42)).is_ok());
+			assert_eq!(TemplateModule::something(), Some(42));
+
+			let transfer = Call::Balances(BalancesCall::transfer(hot.clone(), 1));
+			assert_eq!(
+				as_notary(transfer).map_err(|e| e.error),
+				Err(utility::Error::<Runtime>::Uncallable.into()),
+			);
+		});
+	}
+}
+
+#[cfg(test)]
+mod share_asset_tests {
+	use super::*;
+	use template::AssetProvider;
+
+	const ISSUER: [u8; 32] = [1; 32];
+	const HOLDER: [u8; 32] = [2; 32];
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		system::GenesisConfig::default().build_storage::<Runtime>().unwrap().into()
+	}
+
+	#[test]
+	fn only_the_holder_of_every_unit_holds_all_of_an_asset() {
+		new_test_ext().execute_with(|| {
+			let (issuer, holder) = (AccountId::from(ISSUER), AccountId::from(HOLDER));
+			assert!(!ShareAssets::holds_all_of(&issuer, 0));
+
+			assets::Call::<Runtime>::issue(100).dispatch(Origin::signed(issuer.clone())).unwrap();
+			assert!(ShareAssets::holds_all_of(&issuer, 0));
+			assert!(!ShareAssets::holds_all_of(&holder, 0));
+
+			assets::Call::<Runtime>::transfer(0, holder.clone(), 1)
+				.dispatch(Origin::signed(issuer.clone()))
+				.unwrap();
+			assert!(!ShareAssets::holds_all_of(&issuer, 0));
+			assert!(!ShareAssets::holds_all_of(&holder, 0));
+		});
+	}
+}
+
+#[cfg(test)]
+mod identity_tests {
+	use super::*;
+	use identity::{Data, IdentityInfo, Judgement};
+	use template::IdentityProvider;
+	use crate::DOLLARS;
+
+	const OWNER: [u8; 32] = [1; 32];
+	const REGISTRAR: [u8; 32] = [2; 32];
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		balances::GenesisConfig::<Runtime> {
+			balances: vec![(OWNER.into(), 100 * DOLLARS), (REGISTRAR.into(), 100 * DOLLARS)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn display_name(name: &[u8]) -> IdentityInfo {
+		IdentityInfo {
+			additional: vec![],
+			display: Data::Raw(name.to_vec()),
+			legal: Data::None,
+			web: Data::None,
+			riot: Data::None,
+			email: Data::None,
+			pgp_fingerprint: None,
+			image: Data::None,
+			twitter: Data::None,
+		}
+	}
+
+	fn judge(judgement: Judgement<Balance>) {
+		identity::Call::<Runtime>::provide_judgement(0, OWNER.into(), judgement)
+			.dispatch(Origin::signed(REGISTRAR.into()))
+			.unwrap();
+	}
+
+	#[test]
+	fn only_reasonable_and_known_good_identities_are_verified() {
+		new_test_ext().execute_with(|| {
+			let owner = AccountId::from(OWNER);
+			identity::Call::<Runtime>::add_registrar(REGISTRAR.into())
+				.dispatch(system::RawOrigin::Root.into())
+				.unwrap();
+			identity::Call::<Runtime>::set_identity(display_name(b"Alice"))
+				.dispatch(Origin::signed(owner.clone()))
+				.unwrap();
+			assert_eq!(VerifiedIdentity::verified_display_name(&owner), None);
+
+			identity::Call::<Runtime>::request_judgement(0, 0)
+				.dispatch(Origin::signed(owner.clone()))
+				.unwrap();
+			assert_eq!(Identity::identity(&owner).unwrap().judgements, vec![(0, Judgement::FeePaid(0))]);
+			assert_eq!(VerifiedIdentity::verified_display_name(&owner), None);
+
+			judge(Judgement::Unknown);
+			assert_eq!(VerifiedIdentity::verified_display_name(&owner), None);
+
+			judge(Judgement::Reasonable);
+			assert_eq!(VerifiedIdentity::verified_display_name(&owner), Some(b"Alice".to_vec()));
+
+			judge(Judgement::KnownGood);
+			assert_eq!(VerifiedIdentity::verified_display_name(&owner), Some(b"Alice".to_vec()));
+
+			judge(Judgement::Erroneous);
+			assert_eq!(VerifiedIdentity::verified_display_name(&owner), None);
+		});
+	}
+
+	#[test]
+	fn genesis_registrars_are_added_without_a_fee() {
+		let mut t = system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+		template::GenesisConfig::<Runtime> {
+			identity_registrars: vec![REGISTRAR.into()],
+		}.assimilate_storage(&mut t).unwrap();
+
+		sp_io::TestExternalities::from(t).execute_with(|| {
+			assert_eq!(Identity::registrars(), vec![Some(identity::RegistrarInfo {
+				account: REGISTRAR.into(),
+				fee: 0,
+				fields: Default::default(),
+			})]);
+			assert!(System::events().is_empty());
+		});
+	}
+
+	#[test]
+	#[should_panic(expected = "genesis registrars can be added")]
+	fn genesis_registrars_are_bounded() {
+		let registrars = (0..=MaxRegistrars::get() as u8).map(|i| AccountId::from([i; 32])).collect();
+		let _ = template::GenesisConfig::<Runtime> { identity_registrars: registrars }.build_storage();
+	}
+}
@@ -9,31 +9,42 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use sp_std::prelude::*;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{
+	crypto::KeyTypeId, OpaqueMetadata, H160, H256, U256,
+	u32_trait::{_1, _2, _3, _4, _5},
+};
 use sp_runtime::{
 	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
-	transaction_validity::{TransactionValidity, TransactionSource},
+	FixedPointNumber, ModuleId,
+	curve::PiecewiseLinear,
+	transaction_validity::{TransactionValidity, TransactionSource, TransactionPriority},
 };
 use sp_runtime::traits::{
 	BlakeTwo256, Block as BlockT, IdentityLookup, Verify, IdentifyAccount, NumberFor, Saturating,
+	OpaqueKeys, ConvertInto,
 };
 use sp_api::impl_runtime_apis;
+#[cfg(not(feature = "babe-consensus"))]
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList};
 use grandpa::fg_primitives;
 use sp_version::RuntimeVersion;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
+use session::historical as session_historical;
+use contracts_rpc_runtime_api::ContractExecResult;
+use evm::ConvertAccountId;
 
 // A few exports that help ease life for downstream crates.
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
 pub use timestamp::Call as TimestampCall;
 pub use balances::Call as BalancesCall;
-pub use sp_runtime::{Permill, Perbill};
+pub use staking::{Forcing, StakerStatus};
+pub use sp_runtime::{Permill, Perbill, Percent, Perquintill, Fixed128};
 pub use frame_support::{
 	construct_runtime, parameter_types, StorageValue,
-	traits::{KeyOwnerProofSystem, Randomness},
+	traits::{Get, KeyOwnerProofSystem, OnRuntimeUpgrade, Randomness},
 	weights::{
 		Weight, IdentityFee,
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
@@ -43,6 +54,15 @@ pub use frame_support::{
 /// Importing a template pallet
 pub use template;
 
+/// Implementations of some helper traits passed into runtime modules as associated types.
+pub mod impls;
+#[cfg(not(feature = "babe-consensus"))]
+use impls::AuraAuthorities;
+use impls::{
+	ContractCall, CouncilTippers, CurrencyToVoteHandler, DealWithFees, EnsureOneOf, FixedGasPrice,
+	NotarizationOnly, ShareAssets, TargetedFeeAdjustment, TemplatePrecompiles, VerifiedIdentity, WeightToFee,
+};
+
 /// An index to a block.
 pub type BlockNumber = u32;
 
@@ -85,23 +105,42 @@ pub mod opaque {
 	/// Opaque block identifier type.
 	pub type BlockId = generic::BlockId<Block>;
 
+	#[cfg(not(feature = "babe-consensus"))]
 	impl_opaque_keys! {
 		pub struct SessionKeys {
 			pub aura: Aura,
 			pub grandpa: Grandpa,
 		}
 	}
+
+	#[cfg(feature = "babe-consensus")]
+	impl_opaque_keys! {
+		pub struct SessionKeys {
+			pub babe: Babe,
+			pub grandpa: Grandpa,
+		}
+	}
 }
 
+/// Name of this runtime's specification. It depends on the consensus engine so that a native
+/// runtime and a Wasm binary built with different `babe-consensus` features are told apart.
+#[cfg(not(feature = "babe-consensus"))]
+pub const SPEC_NAME: &str = "node-template";
+#[cfg(feature = "babe-consensus")]
+pub const SPEC_NAME: &str = "node-template-babe";
+
 /// This runtime version.
+///
+/// `node-template metadata-diff` against the previous runtime reports whether a change to
+/// calls or signed extensions requires bumping `transaction_version`.
 pub const VERSION: RuntimeVersion = RuntimeVersion {
-	spec_name: create_runtime_str!("node-template"),
+	spec_name: create_runtime_str!(SPEC_NAME),
 	impl_name: create_runtime_str!("node-template"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 16,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 6,
 };
 
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
@@ -113,6 +152,18 @@ pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
 
+// Currency units, used to size deposits and bonds.
+pub const MILLICENTS: Balance = 1_000_000_000;
+pub const CENTS: Balance = 1_000 * MILLICENTS;
+pub const DOLLARS: Balance = 100 * CENTS;
+
+/// Length of a session, and of a BABE epoch when the `babe-consensus` feature is enabled.
+pub const EPOCH_DURATION_IN_BLOCKS: BlockNumber = 10 * MINUTES;
+pub const EPOCH_DURATION_IN_SLOTS: u64 = EPOCH_DURATION_IN_BLOCKS as u64;
+
+/// 1 in 4 blocks (on average, not counting collisions) will be primary BABE blocks.
+pub const PRIMARY_PROBABILITY: (u64, u64) = (1, 4);
+
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
 pub fn native_version() -> NativeVersion {
@@ -189,10 +240,37 @@ impl system::Trait for Runtime {
 	type AccountData = balances::AccountData<Balance>;
 }
 
+#[cfg(not(feature = "babe-consensus"))]
 impl aura::Trait for Runtime {
 	type AuthorityId = AuraId;
 }
 
+parameter_types! {
+	/// Ten missed slots in a row, i.e. a minute without blocks, report an authority as down.
+	pub const MissedSlotsThreshold: u32 = 10;
+}
+
+#[cfg(not(feature = "babe-consensus"))]
+impl authority_health::Trait for Runtime {
+	type Event = Event;
+	type AuthorityId = AuraId;
+	type Authorities = AuraAuthorities;
+	type MissedSlotsThreshold = MissedSlotsThreshold;
+}
+
+parameter_types! {
+	pub const EpochDuration: u64 = EPOCH_DURATION_IN_SLOTS;
+	pub const ExpectedBlockTime: u64 = MILLISECS_PER_BLOCK;
+}
+
+#[cfg(feature = "babe-consensus")]
+impl babe::Trait for Runtime {
+	type EpochDuration = EpochDuration;
+	type ExpectedBlockTime = ExpectedBlockTime;
+	/// Epochs are changed together with sessions.
+	type EpochChangeTrigger = babe::ExternalTrigger;
+}
+
 impl grandpa::Trait for Runtime {
 	type Event = Event;
 	type Call = Call;
@@ -210,6 +288,127 @@ impl grandpa::Trait for Runtime {
 	type HandleEquivocation = ();
 }
 
+parameter_types! {
+	pub const SessionPeriod: BlockNumber = EPOCH_DURATION_IN_BLOCKS;
+	pub const SessionOffset: BlockNumber = 0;
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(17);
+}
+
+impl session::Trait for Runtime {
+	type Event = Event;
+	/// Validators are identified by their stash account.
+	type ValidatorId = AccountId;
+	type ValidatorIdOf = staking::StashOf<Self>;
+	#[cfg(not(feature = "babe-consensus"))]
+	type ShouldEndSession = session::PeriodicSessions<SessionPeriod, SessionOffset>;
+	#[cfg(not(feature = "babe-consensus"))]
+	type NextSessionRotation = session::PeriodicSessions<SessionPeriod, SessionOffset>;
+	/// BABE ends the session together with its epoch.
+	#[cfg(feature = "babe-consensus")]
+	type ShouldEndSession = Babe;
+	#[cfg(feature = "babe-consensus")]
+	type NextSessionRotation = Babe;
+	/// Staking elects the validators of each era, and the historical pallet keeps their exposure
+	/// around, so that offences in past sessions can still be slashed.
+	type SessionManager = session_historical::NoteHistoricalRoot<Self, Staking>;
+	/// Hands the new authorities to Aura (or BABE) and GRANDPA at each session change.
+	type SessionHandler = <opaque::SessionKeys as OpaqueKeys>::KeyTypeIdProviders;
+	type Keys = opaque::SessionKeys;
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+}
+
+impl session_historical::Trait for Runtime {
+	type FullIdentification = staking::Exposure<AccountId, Balance>;
+	type FullIdentificationOf = staking::ExposureOf<Runtime>;
+}
+
+staking_reward_curve::build! {
+	const REWARD_CURVE: PiecewiseLinear<'static> = curve!(
+		min_inflation: 0_025_000,
+		max_inflation: 0_100_000,
+		ideal_stake: 0_500_000,
+		falloff: 0_050_000,
+		max_piece_count: 40,
+		test_precision: 0_005_000,
+	);
+}
+
+parameter_types! {
+	/// An era lasts 6 sessions, i.e. one hour.
+	pub const SessionsPerEra: sp_staking::SessionIndex = 6;
+	/// 28 days.
+	pub const BondingDuration: staking::EraIndex = 24 * 28;
+	/// 7 days, a quarter of the bonding duration.
+	pub const SlashDeferDuration: staking::EraIndex = 24 * 7;
+	pub const RewardCurve: &'static PiecewiseLinear<'static> = &REWARD_CURVE;
+	pub const ElectionLookahead: BlockNumber = EPOCH_DURATION_IN_BLOCKS / 4;
+	pub const MaxIterations: u32 = 10;
+	pub const MaxNominatorRewardedPerValidator: u32 = 64;
+	pub const StakingUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+}
+
+impl staking::Trait for Runtime {
+	type Currency = Balances;
+	type UnixTime = Timestamp;
+	type CurrencyToVote = CurrencyToVoteHandler;
+	type RewardRemainder = ();
+	type Event = Event;
+	type Slash = ();
+	type Reward = ();
+	type SessionsPerEra = SessionsPerEra;
+	type BondingDuration = BondingDuration;
+	type SlashDeferDuration = SlashDeferDuration;
+	/// Deferred slashes can only be cancelled by root.
+	type SlashCancelOrigin = system::EnsureRoot<AccountId>;
+	type SessionInterface = Self;
+	type RewardCurve = RewardCurve;
+	type NextNewSession = Session;
+	type ElectionLookahead = ElectionLookahead;
+	type Call = Call;
+	type MaxIterations = MaxIterations;
+	type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
+	type UnsignedPriority = StakingUnsignedPriority;
+}
+
+impl<C> system::offchain::SendTransactionTypes<C> for Runtime where
+	Call: From<C>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = UncheckedExtrinsic;
+}
+
+/// Offences are deferred once their on-offence handling would take more than 60% of
+/// `MaximumBlockWeight`. `parameter_types!` only takes constants, hence the manual `Get`.
+pub struct OffencesWeightSoftLimit;
+impl Get<Weight> for OffencesWeightSoftLimit {
+	fn get() -> Weight {
+		Perbill::from_percent(60) * MaximumBlockWeight::get()
+	}
+}
+
+impl offences::Trait for Runtime {
+	type Event = Event;
+	type IdentificationTuple = session_historical::IdentificationTuple<Self>;
+	/// Offences reported by other pallets are slashed by staking.
+	type OnOffenceHandler = Staking;
+	type WeightSoftLimit = OffencesWeightSoftLimit;
+}
+
+parameter_types! {
+	pub const UncleGenerations: BlockNumber = 5;
+}
+
+impl authorship::Trait for Runtime {
+	#[cfg(not(feature = "babe-consensus"))]
+	type FindAuthor = session::FindAccountFromAuthorIndex<Self, Aura>;
+	#[cfg(feature = "babe-consensus")]
+	type FindAuthor = session::FindAccountFromAuthorIndex<Self, Babe>;
+	type UncleGenerations = UncleGenerations;
+	type FilterUncle = ();
+	/// Block authors earn era points towards their staking rewards.
+	type EventHandler = Staking;
+}
+
 parameter_types! {
 	pub const MinimumPeriod: u64 = SLOT_DURATION / 2;
 }
@@ -217,7 +416,10 @@ parameter_types! {
 impl timestamp::Trait for Runtime {
 	/// A timestamp: milliseconds since the unix epoch.
 	type Moment = u64;
+	#[cfg(not(feature = "babe-consensus"))]
 	type OnTimestampSet = Aura;
+	#[cfg(feature = "babe-consensus")]
+	type OnTimestampSet = Babe;
 	type MinimumPeriod = MinimumPeriod;
 }
 
@@ -230,21 +432,280 @@ impl balances::Trait for Runtime {
 	type Balance = Balance;
 	/// The ubiquitous event type.
 	type Event = Event;
-	type DustRemoval = ();
+	/// Balances reaped below the existential deposit go to the treasury.
+	type DustRemoval = Treasury;
 	type ExistentialDeposit = ExistentialDeposit;
 	type AccountStore = System;
 }
 
 parameter_types! {
 	pub const TransactionByteFee: Balance = 1;
+	/// Share of every transaction fee that is paid into the treasury. The rest goes to the
+	/// block author.
+	pub const TreasuryFeeShare: Permill = Permill::from_percent(80);
+	/// Fees go up while blocks are fuller than this share of `MaximumBlockWeight`.
+	pub const TargetBlockFullness: Perquintill = Perquintill::from_percent(25);
+}
+
+/// How fast fees react to block fullness: at most 0.005% per block.
+pub struct AdjustmentVariable;
+impl Get<Fixed128> for AdjustmentVariable {
+	fn get() -> Fixed128 {
+		Fixed128::saturating_from_rational(1, 10_000)
+	}
 }
 
 impl transaction_payment::Trait for Runtime {
 	type Currency = balances::Module<Runtime>;
-	type OnTransactionPayment = ();
+	type OnTransactionPayment = DealWithFees;
 	type TransactionByteFee = TransactionByteFee;
-	type WeightToFee = IdentityFee<Balance>;
-	type FeeMultiplierUpdate = ();
+	type WeightToFee = WeightToFee;
+	type FeeMultiplierUpdate = TargetedFeeAdjustment<TargetBlockFullness, AdjustmentVariable>;
+}
+
+/// Scheduled calls may use up to 80% of `MaximumBlockWeight`.
+pub struct MaximumSchedulerWeight;
+impl Get<Weight> for MaximumSchedulerWeight {
+	fn get() -> Weight {
+		Perbill::from_percent(80) * MaximumBlockWeight::get()
+	}
+}
+
+impl scheduler::Trait for Runtime {
+	type Event = Event;
+	type Origin = Origin;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+}
+
+parameter_types! {
+	pub const CouncilMotionDuration: BlockNumber = 5 * DAYS;
+	pub const CouncilMaxProposals: u32 = 100;
+}
+
+type CouncilCollective = collective::Instance1;
+impl collective::Trait<CouncilCollective> for Runtime {
+	type Origin = Origin;
+	type Proposal = Call;
+	type Event = Event;
+	type MotionDuration = CouncilMotionDuration;
+	type MaxProposals = CouncilMaxProposals;
+}
+
+parameter_types! {
+	pub const TechnicalMotionDuration: BlockNumber = 5 * DAYS;
+	pub const TechnicalMaxProposals: u32 = 100;
+}
+
+type TechnicalCollective = collective::Instance2;
+impl collective::Trait<TechnicalCollective> for Runtime {
+	type Origin = Origin;
+	type Proposal = Call;
+	type Event = Event;
+	type MotionDuration = TechnicalMotionDuration;
+	type MaxProposals = TechnicalMaxProposals;
+}
+
+parameter_types! {
+	pub const LaunchPeriod: BlockNumber = 7 * DAYS;
+	pub const VotingPeriod: BlockNumber = 7 * DAYS;
+	pub const FastTrackVotingPeriod: BlockNumber = 3 * HOURS;
+	pub const InstantAllowed: bool = true;
+	pub const MinimumDeposit: Balance = 100 * DOLLARS;
+	pub const EnactmentPeriod: BlockNumber = 8 * DAYS;
+	pub const CooloffPeriod: BlockNumber = 7 * DAYS;
+	pub const PreimageByteDeposit: Balance = 1 * CENTS;
+	pub const MaxVotes: u32 = 100;
+}
+
+/// Referenda are dispatched with the root origin, so runtime upgrades and every other
+/// root-only call can be enacted by the token holders instead of the sudo key.
+impl democracy::Trait for Runtime {
+	type Proposal = Call;
+	type Event = Event;
+	type Currency = Balances;
+	type EnactmentPeriod = EnactmentPeriod;
+	type LaunchPeriod = LaunchPeriod;
+	type VotingPeriod = VotingPeriod;
+	type MinimumDeposit = MinimumDeposit;
+	/// A straight majority of the council can decide what their next motion is.
+	type ExternalOrigin = collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>;
+	/// A super-majority can have the next scheduled referendum be a straight majority-carries vote.
+	type ExternalMajorityOrigin = collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
+	/// A unanimous council can have the next scheduled referendum be a straight default-carries
+	/// (NTB) vote.
+	type ExternalDefaultOrigin = collective::EnsureProportionAtLeast<_1, _1, AccountId, CouncilCollective>;
+	/// Two thirds of the technical committee can have an ExternalMajority/ExternalDefault vote
+	/// be tabled immediately and with a shorter voting/enactment period.
+	type FastTrackOrigin = collective::EnsureProportionAtLeast<_2, _3, AccountId, TechnicalCollective>;
+	type InstantOrigin = collective::EnsureProportionAtLeast<_1, _1, AccountId, TechnicalCollective>;
+	type InstantAllowed = InstantAllowed;
+	type FastTrackVotingPeriod = FastTrackVotingPeriod;
+	/// To cancel a proposal which has been passed, 2/3 of the council must agree to it.
+	type CancellationOrigin = collective::EnsureProportionAtLeast<_2, _3, AccountId, CouncilCollective>;
+	/// Any single technical committee member may veto a coming council proposal, however they
+	/// can only do it once and it lasts only for the cooloff period.
+	type VetoOrigin = collective::EnsureMember<AccountId, TechnicalCollective>;
+	type CooloffPeriod = CooloffPeriod;
+	type PreimageByteDeposit = PreimageByteDeposit;
+	type OperationalPreimageOrigin = collective::EnsureMember<AccountId, CouncilCollective>;
+	type Slash = ();
+	type Scheduler = Scheduler;
+	type MaxVotes = MaxVotes;
+}
+
+parameter_types! {
+	pub const ProposalBond: Permill = Permill::from_percent(5);
+	pub const ProposalBondMinimum: Balance = 1 * DOLLARS;
+	pub const SpendPeriod: BlockNumber = 1 * DAYS;
+	/// Unspent funds are kept for the chain's maintenance rather than burned.
+	pub const Burn: Permill = Permill::from_percent(0);
+	pub const TipCountdown: BlockNumber = 1 * DAYS;
+	pub const TipFindersFee: Percent = Percent::from_percent(20);
+	pub const TipReportDepositBase: Balance = 1 * DOLLARS;
+	pub const TipReportDepositPerByte: Balance = 1 * CENTS;
+	/// The council size assumed when weighing tips. The council is not bounded, so tip calls
+	/// are underweight if it grows past this.
+	pub const MaxTippers: u32 = 100;
+	pub const TreasuryModuleId: ModuleId = ModuleId(*b"py/trsry");
+}
+
+impl treasury::Trait for Runtime {
+	type ModuleId = TreasuryModuleId;
+	type Currency = Balances;
+	/// Spend proposals are approved by root or by three fifths of the council.
+	type ApproveOrigin = EnsureOneOf<
+		system::EnsureRoot<AccountId>,
+		collective::EnsureProportionAtLeast<_3, _5, AccountId, CouncilCollective>,
+	>;
+	/// Spend proposals are rejected by root or by a majority of the council.
+	type RejectOrigin = EnsureOneOf<
+		system::EnsureRoot<AccountId>,
+		collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>,
+	>;
+	type Tippers = CouncilTippers;
+	type TipCountdown = TipCountdown;
+	type TipFindersFee = TipFindersFee;
+	type TipReportDepositBase = TipReportDepositBase;
+	type TipReportDepositPerByte = TipReportDepositPerByte;
+	type Event = Event;
+	/// Bonds of rejected proposals stay in the treasury.
+	type ProposalRejection = Treasury;
+	type ProposalBond = ProposalBond;
+	type ProposalBondMinimum = ProposalBondMinimum;
+	type SpendPeriod = SpendPeriod;
+	type Burn = Burn;
+}
+
+parameter_types! {
+	pub const MinVestedTransfer: Balance = 1 * DOLLARS;
+}
+
+impl vesting::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BlockNumberToBalance = ConvertInto;
+	type MinVestedTransfer = MinVestedTransfer;
+}
+
+parameter_types! {
+	pub const TombstoneDeposit: Balance = 16 * MILLICENTS;
+	pub const RentByteFee: Balance = 4 * MILLICENTS;
+	pub const RentDepositOffset: Balance = 1000 * MILLICENTS;
+	pub const SurchargeReward: Balance = 150 * MILLICENTS;
+}
+
+impl contracts::Trait for Runtime {
+	type Time = Timestamp;
+	type Randomness = RandomnessCollectiveFlip;
+	/// Contracts dispatch template calls with `ext_dispatch_call` and read template storage,
+	/// such as `template::Module::stored_value_key`, with `ext_get_runtime_storage`.
+	type Call = ContractCall;
+	type Event = Event;
+	type DetermineContractAddress = contracts::SimpleAddressDeterminer<Runtime>;
+	type TrieIdGenerator = contracts::TrieIdFromParentCounter<Runtime>;
+	type RentPayment = ();
+	type SignedClaimHandicap = contracts::DefaultSignedClaimHandicap;
+	type TombstoneDeposit = TombstoneDeposit;
+	type StorageSizeOffset = contracts::DefaultStorageSizeOffset;
+	type RentByteFee = RentByteFee;
+	type RentDepositOffset = RentDepositOffset;
+	type SurchargeReward = SurchargeReward;
+	type MaxDepth = contracts::DefaultMaxDepth;
+	type MaxValueSize = contracts::DefaultMaxValueSize;
+}
+
+parameter_types! {
+	pub const EvmModuleId: ModuleId = ModuleId(*b"py/evmpa");
+}
+
+impl evm::Trait for Runtime {
+	type ModuleId = EvmModuleId;
+	type FeeCalculator = FixedGasPrice;
+	/// EVM addresses are the last 20 bytes of the BLAKE2 hash of the account id.
+	type ConvertAccountId = evm::HashTruncateConvertAccountId<BlakeTwo256>;
+	type Currency = Balances;
+	type Event = Event;
+	type Precompiles = TemplatePrecompiles;
+}
+
+impl assets::Trait for Runtime {
+	type Event = Event;
+	/// Share tokens use the same precision as the native token.
+	type Balance = Balance;
+	type AssetId = u32;
+}
+
+parameter_types! {
+	pub const BasicDeposit: Balance = 10 * DOLLARS;
+	pub const FieldDeposit: Balance = 250 * CENTS;
+	pub const SubAccountDeposit: Balance = 2 * DOLLARS;
+	pub const MaxSubAccounts: u32 = 100;
+	pub const MaxAdditionalFields: u32 = 100;
+	pub const MaxRegistrars: u32 = 20;
+}
+
+impl identity::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BasicDeposit = BasicDeposit;
+	type FieldDeposit = FieldDeposit;
+	type SubAccountDeposit = SubAccountDeposit;
+	type MaxSubAccounts = MaxSubAccounts;
+	type MaxAdditionalFields = MaxAdditionalFields;
+	type MaxRegistrars = MaxRegistrars;
+	/// Deposits of identities removed by force go to the treasury.
+	type Slashed = Treasury;
+	/// Identities are removed by force by root or by a majority of the council.
+	type ForceOrigin = EnsureOneOf<
+		system::EnsureRoot<AccountId>,
+		collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>,
+	>;
+	/// Registrars are added by root or by a majority of the council.
+	type RegistrarOrigin = EnsureOneOf<
+		system::EnsureRoot<AccountId>,
+		collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>,
+	>;
+}
+
+parameter_types! {
+	pub const MultisigDepositBase: Balance = 1 * DOLLARS;
+	pub const MultisigDepositFactor: Balance = 5 * CENTS;
+	pub const MaxSignatories: u16 = 100;
+}
+
+/// Batches, sub-account (`as_sub`) and multisig calls. Hot keys notarize through a multisig
+/// account they share with a cold key, see `NotarizationOnly`.
+impl utility::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type MultisigDepositBase = MultisigDepositBase;
+	type MultisigDepositFactor = MultisigDepositFactor;
+	type MaxSignatories = MaxSignatories;
+	/// Only notarizations may be batched or made by a sub-account or multisig. Root may batch
+	/// any call.
+	type IsCallable = NotarizationOnly;
 }
 
 impl sudo::Trait for Runtime {
@@ -252,29 +713,83 @@ impl sudo::Trait for Runtime {
 	type Call = Call;
 }
 
+parameter_types! {
+	pub const FreeCallWindow: BlockNumber = DAYS;
+	pub const MaxFreeCallsPerWindow: u32 = 1_000;
+	pub const MaxScheduledPerAccount: u32 = 16;
+}
+
 /// Used for the module template in `./template.rs`
 impl template::Trait for Runtime {
 	type Event = Event;
+	type FreeCallWindow = FreeCallWindow;
+	type MaxFreeCallsPerWindow = MaxFreeCallsPerWindow;
+	type Call = Call;
+	type Scheduler = Scheduler;
+	type MaxScheduledPerAccount = MaxScheduledPerAccount;
+	type Identity = VerifiedIdentity;
+	type AssetId = <Runtime as assets::Trait>::AssetId;
+	type Assets = ShareAssets;
 }
 
-construct_runtime!(
-	pub enum Runtime where
-		Block = Block,
-		NodeBlock = opaque::Block,
-		UncheckedExtrinsic = UncheckedExtrinsic
-	{
-		System: system::{Module, Call, Config, Storage, Event<T>},
-		RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
-		Timestamp: timestamp::{Module, Call, Storage, Inherent},
-		Aura: aura::{Module, Config<T>, Inherent(Timestamp)},
-		Grandpa: grandpa::{Module, Call, Storage, Config, Event},
-		Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
-		TransactionPayment: transaction_payment::{Module, Storage},
-		Sudo: sudo::{Module, Call, Config<T>, Storage, Event<T>},
-		// Used for the module template in `./template.rs`
-		TemplateModule: template::{Module, Call, Storage, Event<T>},
+/// Declares `Runtime` with the given block authoring pallet.
+///
+/// `construct_runtime!` does not accept `#[cfg]` on its entries, so the Aura and BABE
+/// variants hand their consensus pallet in through this macro.
+macro_rules! construct_runtime_with_consensus {
+	( $( $consensus:tt )* ) => {
+		construct_runtime!(
+			pub enum Runtime where
+				Block = Block,
+				NodeBlock = opaque::Block,
+				UncheckedExtrinsic = UncheckedExtrinsic
+			{
+				System: system::{Module, Call, Config, Storage, Event<T>},
+				RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
+				Timestamp: timestamp::{Module, Call, Storage, Inherent},
+				// The consensus pallet must be initialized before the session pallet asks it
+				// whether the session should end.
+				$( $consensus )*
+				Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
+				TransactionPayment: transaction_payment::{Module, Storage},
+				Authorship: authorship::{Module, Call, Storage, Inherent},
+				// Staking must come before session, so that the genesis stakers are elected
+				// when the session pallet asks for the first validator set.
+				Staking: staking::{Module, Call, Config<T>, Storage, Event<T>, ValidateUnsigned},
+				Offences: offences::{Module, Call, Storage, Event},
+				Session: session::{Module, Call, Storage, Event, Config<T>},
+				Historical: session_historical::{Module},
+				Grandpa: grandpa::{Module, Call, Storage, Config, Event},
+				Scheduler: scheduler::{Module, Call, Storage, Event<T>},
+				Democracy: democracy::{Module, Call, Storage, Config, Event<T>},
+				Council: collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+				TechnicalCommittee: collective::<Instance2>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+				Treasury: treasury::{Module, Call, Storage, Config, Event<T>},
+				Vesting: vesting::{Module, Call, Storage, Event<T>, Config<T>},
+				Contracts: contracts::{Module, Call, Config, Storage, Event<T>},
+				EVM: evm::{Module, Call, Storage, Config, Event<T>},
+				Assets: assets::{Module, Call, Storage, Event<T>},
+				Identity: identity::{Module, Call, Storage, Event<T>},
+				Utility: utility::{Module, Call, Storage, Event<T>},
+				Sudo: sudo::{Module, Call, Config<T>, Storage, Event<T>},
+				// Used for the module template in `./template.rs`
+				TemplateModule: template::{Module, Call, Storage, Event<T>, Config<T>},
+			}
+		);
 	}
-);
+}
+
+#[cfg(not(feature = "babe-consensus"))]
+construct_runtime_with_consensus! {
+	Aura: aura::{Module, Config<T>, Inherent(Timestamp)},
+	// Reads the authorities before a session change in the block replaces them.
+	AuthorityHealth: authority_health::{Module, Storage, Event<T>},
+}
+
+#[cfg(feature = "babe-consensus")]
+construct_runtime_with_consensus! {
+	Babe: babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
+}
 
 /// The address format for describing accounts.
 pub type Address = AccountId;
@@ -294,7 +809,8 @@ pub type SignedExtra = (
 	system::CheckEra<Runtime>,
 	system::CheckNonce<Runtime>,
 	system::CheckWeight<Runtime>,
-	transaction_payment::ChargeTransactionPayment<Runtime>
+	// Pays for a daily free template call, then with notary credits or the usual fee.
+	template::FreeFirstCall<Runtime>
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -303,67 +819,258 @@ pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExt
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<Runtime, Block, system::ChainContext<Runtime>, Runtime, AllModules>;
 
-impl_runtime_apis! {
-	impl sp_api::Core<Block> for Runtime {
-		fn version() -> RuntimeVersion {
-			VERSION
-		}
+/// The runtime APIs of the Aura runtime only.
+#[cfg(not(feature = "babe-consensus"))]
+pub mod aura_apis {
+	use super::*;
 
-		fn execute_block(block: Block) {
-			Executive::execute_block(block)
+	sp_api::decl_runtime_apis! {
+		/// Reports how reliably the Aura authorities author blocks.
+		pub trait AuthorityHealthApi {
+			/// The authored and missed slots of each current authority.
+			fn authority_stats() -> Vec<(AuraId, authority_health::AuthorityStats)>;
 		}
+	}
+}
+#[cfg(not(feature = "babe-consensus"))]
+pub use aura_apis::AuthorityHealthApi;
 
-		fn initialize_block(header: &<Block as BlockT>::Header) {
-			Executive::initialize_block(header)
-		}
+sp_api::decl_runtime_apis! {
+	/// Dry-runs runtime upgrades, used by the node's `try-runtime` subcommand.
+	pub trait TryRuntime {
+		/// Run the `on_runtime_upgrade` hooks of all pallets, followed by the post-upgrade
+		/// checks. Returns the weight consumed and the messages of the failed checks.
+		fn on_runtime_upgrade() -> (Weight, Vec<Vec<u8>>);
 	}
 
-	impl sp_api::Metadata<Block> for Runtime {
-		fn metadata() -> OpaqueMetadata {
-			Runtime::metadata().into()
-		}
+	/// Queries of the template pallet.
+	pub trait TemplateApi {
+		/// The value stored with `do_something`, if any.
+		fn something() -> Option<u32>;
+		/// The verified display name of `owner`, if a registrar judged its identity to be correct.
+		fn owner_display_name(owner: AccountId) -> Option<Vec<u8>>;
 	}
 
-	impl sp_block_builder::BlockBuilder<Block> for Runtime {
-		fn apply_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> ApplyExtrinsicResult {
-			Executive::apply_extrinsic(extrinsic)
-		}
+	/// Ethereum style access to the EVM, used by the node's `evm_*` RPC methods.
+	pub trait EvmRuntimeApi {
+		/// The EVM address of `account`.
+		fn address_of(account: AccountId) -> H160;
+		/// The nonce and balance of the EVM account at `address`.
+		fn account_basic(address: H160) -> evm::Account;
+		/// The code of the contract at `address`.
+		fn account_code(address: H160) -> Vec<u8>;
+		/// Slot `index` of the storage of the contract at `address`.
+		fn storage_at(address: H160, index: H256) -> H256;
+		/// Dry-run a call from `from` to `to`. Returns whether it succeeded and the gas it used.
+		fn call(from: H160, to: H160, input: Vec<u8>, value: U256, gas_limit: u32) -> (bool, U256);
+	}
+}
 
-		fn finalize_block() -> <Block as BlockT>::Header {
-			Executive::finalize_block()
-		}
+/// Checks that the storage is consistent after `on_runtime_upgrade`. Returns the failures.
+fn post_upgrade_checks() -> Vec<Vec<u8>> {
+	let checks: &[fn() -> Result<(), &'static str>] = &[
+		template::migrations::post_upgrade::<Runtime>,
+	];
+	checks.iter()
+		.filter_map(|check| check().err())
+		.map(|failure| failure.as_bytes().to_vec())
+		.collect()
+}
 
-		fn inherent_extrinsics(data: sp_inherents::InherentData) -> Vec<<Block as BlockT>::Extrinsic> {
-			data.create_extrinsics()
-		}
+/// Implements the runtime APIs, including the one of the given block authoring pallet.
+///
+/// Like `construct_runtime_with_consensus!`, this lets the Aura and BABE variants share
+/// every other API implementation.
+macro_rules! impl_runtime_apis_with_consensus {
+	( $( $consensus:tt )* ) => {
+		impl_runtime_apis! {
+			impl sp_api::Core<Block> for Runtime {
+				fn version() -> RuntimeVersion {
+					VERSION
+				}
 
-		fn check_inherents(
-			block: Block,
-			data: sp_inherents::InherentData,
-		) -> sp_inherents::CheckInherentsResult {
-			data.check_extrinsics(&block)
-		}
+				fn execute_block(block: Block) {
+					Executive::execute_block(block)
+				}
 
-		fn random_seed() -> <Block as BlockT>::Hash {
-			RandomnessCollectiveFlip::random_seed()
-		}
-	}
+				fn initialize_block(header: &<Block as BlockT>::Header) {
+					Executive::initialize_block(header)
+				}
+			}
 
-	impl sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block> for Runtime {
-		fn validate_transaction(
-			source: TransactionSource,
-			tx: <Block as BlockT>::Extrinsic,
-		) -> TransactionValidity {
-			Executive::validate_transaction(source, tx)
-		}
-	}
+			impl sp_api::Metadata<Block> for Runtime {
+				fn metadata() -> OpaqueMetadata {
+					Runtime::metadata().into()
+				}
+			}
+
+			impl sp_block_builder::BlockBuilder<Block> for Runtime {
+				fn apply_extrinsic(extrinsic: <Block as BlockT>::Extrinsic) -> ApplyExtrinsicResult {
+					Executive::apply_extrinsic(extrinsic)
+				}
+
+				fn finalize_block() -> <Block as BlockT>::Header {
+					Executive::finalize_block()
+				}
+
+				fn inherent_extrinsics(data: sp_inherents::InherentData) -> Vec<<Block as BlockT>::Extrinsic> {
+					data.create_extrinsics()
+				}
+
+				fn check_inherents(
+					block: Block,
+					data: sp_inherents::InherentData,
+				) -> sp_inherents::CheckInherentsResult {
+					data.check_extrinsics(&block)
+				}
+
+				fn random_seed() -> <Block as BlockT>::Hash {
+					RandomnessCollectiveFlip::random_seed()
+				}
+			}
+
+			impl sp_transaction_pool::runtime_api::TaggedTransactionQueue<Block> for Runtime {
+				fn validate_transaction(
+					source: TransactionSource,
+					tx: <Block as BlockT>::Extrinsic,
+				) -> TransactionValidity {
+					Executive::validate_transaction(source, tx)
+				}
+			}
+
+			impl sp_offchain::OffchainWorkerApi<Block> for Runtime {
+				fn offchain_worker(header: &<Block as BlockT>::Header) {
+					Executive::offchain_worker(header)
+				}
+			}
 
-	impl sp_offchain::OffchainWorkerApi<Block> for Runtime {
-		fn offchain_worker(header: &<Block as BlockT>::Header) {
-			Executive::offchain_worker(header)
+			$( $consensus )*
+
+			impl sp_session::SessionKeys<Block> for Runtime {
+				fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
+					opaque::SessionKeys::generate(seed)
+				}
+
+				fn decode_session_keys(
+					encoded: Vec<u8>,
+				) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
+					opaque::SessionKeys::decode_into_raw_public_keys(&encoded)
+				}
+			}
+
+			impl fg_primitives::GrandpaApi<Block> for Runtime {
+				fn grandpa_authorities() -> GrandpaAuthorityList {
+					Grandpa::grandpa_authorities()
+				}
+
+				fn submit_report_equivocation_extrinsic(
+					_equivocation_proof: fg_primitives::EquivocationProof<
+						<Block as BlockT>::Hash,
+						NumberFor<Block>,
+					>,
+					_key_owner_proof: fg_primitives::OpaqueKeyOwnershipProof,
+				) -> Option<()> {
+					None
+				}
+
+				fn generate_key_ownership_proof(
+					_set_id: fg_primitives::SetId,
+					_authority_id: GrandpaId,
+				) -> Option<fg_primitives::OpaqueKeyOwnershipProof> {
+					// NOTE: this is the only implementation possible since we've
+					// defined our key owner proof type as a bottom type (i.e. a type
+					// with no values).
+					None
+				}
+			}
+
+			impl crate::TryRuntime<Block> for Runtime {
+				fn on_runtime_upgrade() -> (Weight, Vec<Vec<u8>>) {
+					// The same hooks, in the same order, as `Executive` runs after an upgrade.
+					let weight = <System as OnRuntimeUpgrade>::on_runtime_upgrade()
+						.saturating_add(<AllModules as OnRuntimeUpgrade>::on_runtime_upgrade());
+					(weight, post_upgrade_checks())
+				}
+			}
+
+			impl crate::TemplateApi<Block> for Runtime {
+				fn something() -> Option<u32> {
+					TemplateModule::something()
+				}
+
+				fn owner_display_name(owner: AccountId) -> Option<Vec<u8>> {
+					TemplateModule::owner_display_name(&owner)
+				}
+			}
+
+			impl crate::EvmRuntimeApi<Block> for Runtime {
+				fn address_of(account: AccountId) -> H160 {
+					<Runtime as evm::Trait>::ConvertAccountId::convert_account_id(&account)
+				}
+
+				fn account_basic(address: H160) -> evm::Account {
+					EVM::accounts(address)
+				}
+
+				fn account_code(address: H160) -> Vec<u8> {
+					impls::evm_account_code(address)
+				}
+
+				fn storage_at(address: H160, index: H256) -> H256 {
+					impls::evm_storage_at(address, index)
+				}
+
+				fn call(
+					from: H160,
+					to: H160,
+					input: Vec<u8>,
+					value: U256,
+					gas_limit: u32,
+				) -> (bool, U256) {
+					impls::dry_run_evm_call(from, to, input, value, gas_limit)
+				}
+			}
+
+			impl contracts_rpc_runtime_api::ContractsApi<Block, AccountId, Balance, BlockNumber>
+				for Runtime
+			{
+				fn call(
+					origin: AccountId,
+					dest: AccountId,
+					value: Balance,
+					gas_limit: u64,
+					input_data: Vec<u8>,
+				) -> ContractExecResult {
+					let exec_result =
+						Contracts::bare_call(origin, dest.into(), value, gas_limit, input_data);
+					match exec_result {
+						Ok(v) => ContractExecResult::Success {
+							status: v.status,
+							data: v.data,
+						},
+						Err(_) => ContractExecResult::Error,
+					}
+				}
+
+				fn get_storage(
+					address: AccountId,
+					key: [u8; 32],
+				) -> contracts_primitives::GetStorageResult {
+					Contracts::get_storage(address, key)
+				}
+
+				fn rent_projection(
+					address: AccountId,
+				) -> contracts_primitives::RentProjectionResult<BlockNumber> {
+					Contracts::rent_projection(address)
+				}
+			}
 		}
 	}
+}
 
+#[cfg(not(feature = "babe-consensus"))]
+impl_runtime_apis_with_consensus! {
 	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
 		fn slot_duration() -> u64 {
 			Aura::slot_duration()
@@ -374,41 +1081,34 @@ impl_runtime_apis! {
 		}
 	}
 
-	impl sp_session::SessionKeys<Block> for Runtime {
-		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
-			opaque::SessionKeys::generate(seed)
-		}
-
-		fn decode_session_keys(
-			encoded: Vec<u8>,
-		) -> Option<Vec<(Vec<u8>, KeyTypeId)>> {
-			opaque::SessionKeys::decode_into_raw_public_keys(&encoded)
+	impl crate::aura_apis::AuthorityHealthApi<Block> for Runtime {
+		fn authority_stats() -> Vec<(AuraId, authority_health::AuthorityStats)> {
+			AuthorityHealth::authority_stats()
 		}
 	}
+}
 
-	impl fg_primitives::GrandpaApi<Block> for Runtime {
-		fn grandpa_authorities() -> GrandpaAuthorityList {
-			Grandpa::grandpa_authorities()
-		}
-
-		fn submit_report_equivocation_extrinsic(
-			_equivocation_proof: fg_primitives::EquivocationProof<
-				<Block as BlockT>::Hash,
-				NumberFor<Block>,
-			>,
-			_key_owner_proof: fg_primitives::OpaqueKeyOwnershipProof,
-		) -> Option<()> {
-			None
+#[cfg(feature = "babe-consensus")]
+impl_runtime_apis_with_consensus! {
+	impl sp_consensus_babe::BabeApi<Block> for Runtime {
+		fn configuration() -> sp_consensus_babe::BabeGenesisConfiguration {
+			// The choice of `c` parameter (where `1 - c` represents the
+			// probability of a slot being empty), is done in accordance to the
+			// slot duration and expected target block time, for safely
+			// resisting network delays of maximum two seconds.
+			// <https://research.web3.foundation/en/latest/polkadot/BABE/Babe/#6-practical-results>
+			sp_consensus_babe::BabeGenesisConfiguration {
+				slot_duration: Babe::slot_duration(),
+				epoch_length: EpochDuration::get(),
+				c: PRIMARY_PROBABILITY,
+				genesis_authorities: Babe::authorities(),
+				randomness: Babe::randomness(),
+				allowed_slots: sp_consensus_babe::AllowedSlots::PrimaryAndSecondaryPlainSlots,
+			}
 		}
 
-		fn generate_key_ownership_proof(
-			_set_id: fg_primitives::SetId,
-			_authority_id: GrandpaId,
-		) -> Option<fg_primitives::OpaqueKeyOwnershipProof> {
-			// NOTE: this is the only implementation possible since we've
-			// defined our key owner proof type as a bottom type (i.e. a type
-			// with no values).
-			None
+		fn current_epoch_start() -> sp_consensus_babe::SlotNumber {
+			Babe::current_epoch_start()
 		}
 	}
 }